@@ -0,0 +1,364 @@
+use std::fmt::Write;
+use std::rc::Rc;
+
+use log::info;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use url::Url;
+
+use crate::db::Db;
+use crate::header::{Header, Status};
+use crate::mime_types;
+use crate::response::Response;
+use crate::settings::Settings;
+use crate::transfer::format_size;
+
+pub const ABOUT_SCHEME: &str = "about";
+
+const HISTORY_LIMIT: u32 = 500;
+
+pub struct AboutClient {
+    db: Rc<Db>,
+    settings: Settings,
+}
+
+impl std::fmt::Debug for AboutClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AboutClient")
+    }
+}
+
+impl AboutClient {
+    pub fn new(db: Rc<Db>, settings: Settings) -> Self {
+        Self { db, settings }
+    }
+
+    pub fn get(&self, url: &Url) -> anyhow::Result<Response> {
+        info!("generating about page: {}", url.to_string());
+
+        let body = match url.path() {
+            "blank" => String::new(),
+            "bookmarks" => self.bookmarks(url.query())?,
+            "certificates" => self.certificates()?,
+            "history" => self.history(url.query())?,
+            "settings" => self.settings()?,
+            page => anyhow::bail!("unknown about page: {}", page),
+        };
+
+        let header = Header::success(Status::Success, mime_types::gemini());
+
+        Ok(Response::new(header, Some(body.into_bytes()), url))
+    }
+
+    // about:bookmarks?tag only lists bookmarks carrying that tag
+    fn bookmarks(&self, query: Option<&str>) -> anyhow::Result<String> {
+        let mut page = String::from("# Bookmarks\n\n");
+
+        let tag = query.map(|query| percent_decode_str(query).decode_utf8_lossy().to_string());
+
+        let bookmarks: Vec<_> = self
+            .db
+            .get_bookmarks()?
+            .into_iter()
+            .filter(|bookmark| match &tag {
+                Some(tag) => bookmark.tags().contains(&tag.as_str()),
+                None => true,
+            })
+            .collect();
+
+        if let Some(tag) = &tag {
+            writeln!(page, "Bookmarks tagged \"{}\"", tag)?;
+        } else if bookmarks.is_empty() {
+            writeln!(page, "No bookmarks have been saved yet.")?;
+        }
+
+        let mut folder = None;
+
+        for bookmark in &bookmarks {
+            if folder != Some(&bookmark.folder) {
+                let name = match bookmark.folder.as_str() {
+                    "" => "Unsorted",
+                    name => name,
+                };
+
+                writeln!(page, "\n## {}\n", name)?;
+                folder = Some(&bookmark.folder);
+            }
+
+            writeln!(page, "=> {} {}", bookmark.url, bookmark.title)?;
+
+            // each tag links to the bookmarks sharing it
+            for tag in bookmark.tags() {
+                writeln!(
+                    page,
+                    "=> about:bookmarks?{} #{}",
+                    utf8_percent_encode(tag, NON_ALPHANUMERIC),
+                    tag
+                )?;
+            }
+        }
+
+        Ok(page)
+    }
+
+    fn certificates(&self) -> anyhow::Result<String> {
+        let mut page = String::from("# Certificates\n\n");
+
+        let certificates = self.db.get_certificates()?;
+
+        if certificates.is_empty() {
+            writeln!(page, "No certificates have been trusted yet.")?;
+        }
+
+        for certificate in certificates {
+            writeln!(page, "## {}", certificate.hostname)?;
+            writeln!(
+                page,
+                "=> gemini://{}/ {}",
+                certificate.hostname, certificate.hostname
+            )?;
+            writeln!(page, "* Fingerprint: {}", certificate.fingerprint)?;
+            writeln!(page, "* First seen: {}", certificate.first_seen.date())?;
+            writeln!(page, "* Last seen: {}", certificate.last_seen.date())?;
+            writeln!(page)?;
+        }
+
+        Ok(page)
+    }
+
+    // about:history?term only lists pages whose url or title contain the term
+    fn history(&self, query: Option<&str>) -> anyhow::Result<String> {
+        let mut page = String::from("# History\n\n");
+
+        let query = query
+            .map(|query| percent_decode_str(query).decode_utf8_lossy().to_string())
+            .unwrap_or_default();
+
+        let entries = self.db.search_history(&query, HISTORY_LIMIT)?;
+
+        if !query.is_empty() {
+            writeln!(page, "Pages matching \"{}\"", query)?;
+        } else if entries.is_empty() {
+            writeln!(page, "No history has been recorded yet.")?;
+        }
+
+        let mut day = None;
+
+        for entry in entries {
+            let date = entry.visited.date();
+
+            if day != Some(date) {
+                writeln!(page, "\n## {}\n", date)?;
+                day = Some(date);
+            }
+
+            writeln!(
+                page,
+                "=> {} {} ({} visits)",
+                entry.url,
+                entry.title.as_deref().unwrap_or(&entry.url),
+                entry.visits
+            )?;
+        }
+
+        Ok(page)
+    }
+
+    fn settings(&self) -> anyhow::Result<String> {
+        let mut page = String::from("# Settings\n\n");
+
+        writeln!(
+            page,
+            "=> {} Home page",
+            self.settings.default_url_as_string()
+        )?;
+        writeln!(page, "* Database: {}", self.settings.database_path())?;
+        writeln!(
+            page,
+            "* Download directory: {}",
+            self.settings.download_directory()
+        )?;
+        writeln!(
+            page,
+            "* Maximum document size: {}",
+            format_size(self.settings.max_document_size())
+        )?;
+        writeln!(
+            page,
+            "* Open folder command: {}",
+            self.settings.open_folder_command()
+        )?;
+        writeln!(
+            page,
+            "* Inline images: {} (up to {}px)",
+            self.settings.inline_images(),
+            self.settings.max_image_dimension()
+        )?;
+        writeln!(
+            page,
+            "* Retries: {} attempts, waiting up to {:?}",
+            self.settings.retry_policy().max_attempts(),
+            self.settings.retry_policy().max_delay()
+        )?;
+        writeln!(page, "* Cache lifetime: {:?}", self.settings.cache_ttl())?;
+        writeln!(page, "* Header parsing: {:?}", self.settings.header_mode())?;
+        writeln!(
+            page,
+            "* Client certificate: {}",
+            match self.settings.client_identity_paths() {
+                Some((certificate_path, _)) => certificate_path,
+                None => "none".to_string(),
+            }
+        )?;
+
+        writeln!(page, "\n## Handlers\n")?;
+
+        for handler in self.settings.handlers().handlers() {
+            writeln!(page, "* {}: {}", handler.pattern(), handler.command())?;
+        }
+
+        Ok(page)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn about_client() -> AboutClient {
+        let db = Db::new(":memory:").unwrap();
+        db.prepare().unwrap();
+
+        AboutClient::new(Rc::new(db), Settings::new())
+    }
+
+    fn body(response: &Response) -> String {
+        String::from_utf8(response.body().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_about_blank() {
+        let response = about_client().get(&"about:blank".parse().unwrap()).unwrap();
+
+        assert_eq!("", body(&response));
+    }
+
+    #[test]
+    fn test_about_certificates() {
+        let client = about_client();
+        client
+            .db
+            .insert_certificate("example.org", "abcdef")
+            .unwrap();
+
+        let response = client.get(&"about:certificates".parse().unwrap()).unwrap();
+
+        assert!(body(&response).contains("=> gemini://example.org/ example.org\n"));
+        assert!(body(&response).contains("* Fingerprint: abcdef\n"));
+    }
+
+    #[test]
+    fn test_about_bookmarks() {
+        let client = about_client();
+        client
+            .db
+            .insert_bookmark("gemini://example.org/", "Example", "gemini, to read", "")
+            .unwrap();
+        client
+            .db
+            .insert_bookmark("gemini://example.com/", "Other", "", "Reading")
+            .unwrap();
+
+        let response = client.get(&"about:bookmarks".parse().unwrap()).unwrap();
+
+        assert!(body(&response).contains(
+            "## Unsorted\n\n=> gemini://example.org/ Example\n=> about:bookmarks?gemini #gemini\n=> about:bookmarks?to%20read #to read\n"
+        ));
+        assert!(body(&response).contains("## Reading\n\n=> gemini://example.com/ Other\n"));
+
+        let response = client
+            .get(&"about:bookmarks?to%20read".parse().unwrap())
+            .unwrap();
+
+        assert!(body(&response).contains("Bookmarks tagged \"to read\""));
+        assert!(body(&response).contains("gemini://example.org/"));
+        assert!(!body(&response).contains("gemini://example.com/"));
+    }
+
+    #[test]
+    fn test_about_history() {
+        let client = about_client();
+        let now = time::OffsetDateTime::now_utc();
+
+        client
+            .db
+            .record_visit("gemini://example.org/", "example.org", Some("Example"), now)
+            .unwrap();
+        client
+            .db
+            .record_visit("gemini://example.org/", "example.org", None, now)
+            .unwrap();
+        client
+            .db
+            .record_visit("gemini://example.com/", "example.com", None, now)
+            .unwrap();
+
+        let response = client.get(&"about:history".parse().unwrap()).unwrap();
+
+        assert!(body(&response).contains("=> gemini://example.org/ Example (2 visits)\n"));
+        assert!(
+            body(&response).contains("=> gemini://example.com/ gemini://example.com/ (1 visits)\n")
+        );
+
+        let response = client
+            .get(&"about:history?EXAMPLE.com".parse().unwrap())
+            .unwrap();
+
+        assert!(!body(&response).contains("gemini://example.org/"));
+
+        client.db.delete_history_host("example.com").unwrap();
+
+        let response = client.get(&"about:history".parse().unwrap()).unwrap();
+
+        assert!(!body(&response).contains("gemini://example.com/"));
+    }
+
+    #[test]
+    fn test_about_history_clear_range() {
+        let client = about_client();
+        let now = time::OffsetDateTime::now_utc();
+        let last_week = now - time::Duration::weeks(1);
+
+        client
+            .db
+            .record_visit("gemini://example.org/", "example.org", None, last_week)
+            .unwrap();
+        client
+            .db
+            .record_visit("gemini://example.org/", "example.org", None, now)
+            .unwrap();
+        client
+            .db
+            .record_visit("gemini://example.com/", "example.com", None, now)
+            .unwrap();
+
+        client
+            .db
+            .clear_history(now - time::Duration::hours(1))
+            .unwrap();
+
+        let response = client.get(&"about:history".parse().unwrap()).unwrap();
+
+        assert!(body(&response).contains(&format!(
+            "## {}\n\n=> gemini://example.org/ gemini://example.org/ (1 visits)\n",
+            last_week.date()
+        )));
+        assert!(!body(&response).contains("gemini://example.com/"));
+    }
+
+    #[test]
+    fn test_about_unknown() {
+        assert!(about_client()
+            .get(&"about:nothing".parse().unwrap())
+            .is_err());
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::anyhow;
+use log::info;
+use quick_xml::events::Event;
+use url::Url;
+
+use crate::db::model::Bookmark;
+
+// tags lagrange uses to flag bookmarks internally, rather than ones the user picked
+const LAGRANGE_FLAGS: [&str; 6] = [
+    "usericon",
+    "homepage",
+    "remotesource",
+    "remote",
+    "subscribed",
+    "headings",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedBookmark {
+    pub url: String,
+    pub title: String,
+    pub tags: String,
+    pub folder: String,
+}
+
+impl ImportedBookmark {
+    // titles and folders end up on a single gemtext line, so line breaks become spaces
+    fn new(url: &str, title: &str, folder: &str) -> Self {
+        let title = match collapse_whitespace(title) {
+            title if title.is_empty() => url.to_string(),
+            title => title,
+        };
+
+        Self {
+            url: url.to_string(),
+            title,
+            tags: String::new(),
+            folder: collapse_whitespace(folder),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Format {
+    // bookmarks.ini
+    Lagrange,
+    // bookmarks.xml, amfora writes xbel
+    Xbel,
+    // bookmarks.gmi, as written by av-98 and by export
+    Gemtext,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "ini" => Some(Self::Lagrange),
+            "xml" | "xbel" => Some(Self::Xbel),
+            "gmi" | "gemini" => Some(Self::Gemtext),
+            _ => None,
+        }
+    }
+}
+
+pub fn import(path: &Path) -> anyhow::Result<Vec<ImportedBookmark>> {
+    info!("importing bookmarks from: {}", path.display());
+
+    let format = Format::from_path(path)
+        .ok_or_else(|| anyhow!("unknown bookmarks format: {}", path.display()))?;
+
+    let input = std::fs::read_to_string(path)?;
+
+    let bookmarks = match format {
+        Format::Lagrange => parse_lagrange(&input),
+        Format::Xbel => parse_xbel(&input)?,
+        Format::Gemtext => parse_gemtext(&input),
+    };
+
+    // anything that isn't a url would only be a broken link later
+    Ok(bookmarks
+        .into_iter()
+        .filter(|bookmark| Url::parse(&bookmark.url).is_ok())
+        .collect())
+}
+
+// bookmarks are grouped under a heading per folder, unsorted ones come first
+pub fn export(bookmarks: &[Bookmark]) -> String {
+    let mut page = String::from("# Bookmarks\n");
+    let mut folder = None;
+
+    for bookmark in bookmarks {
+        if folder != Some(&bookmark.folder) {
+            if bookmark.folder.is_empty() {
+                page.push('\n');
+            } else {
+                let _ = writeln!(page, "\n## {}\n", bookmark.folder);
+            }
+
+            folder = Some(&bookmark.folder);
+        }
+
+        let _ = writeln!(page, "=> {} {}", bookmark.url, bookmark.title);
+    }
+
+    page
+}
+
+// [id] sections with key = "value" pairs, entries without a url are folders
+fn parse_lagrange(input: &str) -> Vec<ImportedBookmark> {
+    let mut sections: Vec<(String, HashMap<String, String>)> = vec![];
+
+    for line in input.lines().map(str::trim) {
+        if let Some(id) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((id.to_string(), HashMap::new()));
+        } else if let (Some((key, value)), Some((_, fields))) =
+            (line.split_once('='), sections.last_mut())
+        {
+            fields.insert(key.trim().to_string(), unquote(value.trim()));
+        }
+    }
+
+    let entries: HashMap<&str, &HashMap<String, String>> = sections
+        .iter()
+        .map(|(id, fields)| (id.as_str(), fields))
+        .collect();
+
+    let mut bookmarks: Vec<(i64, ImportedBookmark)> = sections
+        .iter()
+        .filter_map(|(_, fields)| {
+            let url = fields.get("url")?;
+            let title = fields.get("title").map(String::as_str).unwrap_or_default();
+            let folder = lagrange_folder(&entries, fields);
+
+            let tags: Vec<_> = fields
+                .get("tags")
+                .map(String::as_str)
+                .unwrap_or_default()
+                .split_whitespace()
+                .filter(|tag| !tag.starts_with('.') && !LAGRANGE_FLAGS.contains(tag))
+                .collect();
+
+            let order = fields
+                .get("order")
+                .and_then(|order| order.parse().ok())
+                .unwrap_or_default();
+
+            Some((
+                order,
+                ImportedBookmark {
+                    tags: tags.join(", "),
+                    ..ImportedBookmark::new(url, title, &folder)
+                },
+            ))
+        })
+        .collect();
+
+    bookmarks.sort_by_key(|(order, _)| *order);
+    bookmarks
+        .into_iter()
+        .map(|(_, bookmark)| bookmark)
+        .collect()
+}
+
+// nested folders become a slash separated path
+fn lagrange_folder(
+    entries: &HashMap<&str, &HashMap<String, String>>,
+    fields: &HashMap<String, String>,
+) -> String {
+    let mut path = vec![];
+    let mut parent = fields.get("parent");
+
+    // the depth limit guards against parents that point at each other
+    while let Some(folder) = parent.and_then(|id| entries.get(id.as_str())) {
+        if path.len() > 16 {
+            break;
+        }
+
+        path.push(folder.get("title").map(String::as_str).unwrap_or_default());
+        parent = folder.get("parent");
+    }
+
+    path.reverse();
+    path.join("/")
+}
+
+fn collapse_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(value) => value
+            .replace("\\\"", "\"")
+            .replace("\\n", "\n")
+            .replace("\\\\", "\\"),
+        None => value.to_string(),
+    }
+}
+
+fn parse_xbel(input: &str) -> anyhow::Result<Vec<ImportedBookmark>> {
+    let mut reader = quick_xml::Reader::from_str(input);
+    reader.trim_text(true);
+
+    let mut bookmarks = vec![];
+    let mut folders: Vec<String> = vec![];
+    let mut current: Option<ImportedBookmark> = None;
+    let mut in_title = false;
+    let mut buf = vec![];
+
+    loop {
+        match reader.read_event(&mut buf)? {
+            Event::Start(e) if e.name() == b"bookmark" => {
+                let href = xbel_href(&e, &reader)?;
+
+                current = Some(ImportedBookmark::new(&href, "", &folders.join("/")));
+            }
+            // <bookmark href="..."/> has no title and no end tag
+            Event::Empty(e) if e.name() == b"bookmark" => {
+                let href = xbel_href(&e, &reader)?;
+
+                bookmarks.push(ImportedBookmark::new(&href, "", &folders.join("/")));
+            }
+            Event::Start(e) if e.name() == b"folder" => folders.push(String::new()),
+            Event::Start(e) if e.name() == b"title" => in_title = true,
+            Event::Text(e) if in_title => {
+                let title = collapse_whitespace(&e.unescape_and_decode(&reader)?);
+
+                match (&mut current, folders.last_mut()) {
+                    (Some(bookmark), _) => bookmark.title = title,
+                    (None, Some(folder)) => *folder = title,
+                    (None, None) => {}
+                }
+            }
+            Event::End(e) if e.name() == b"title" => in_title = false,
+            Event::End(e) if e.name() == b"folder" => {
+                folders.pop();
+            }
+            Event::End(e) if e.name() == b"bookmark" => {
+                bookmarks.extend(current.take());
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+
+        buf.clear();
+    }
+
+    Ok(bookmarks)
+}
+
+fn xbel_href(
+    element: &quick_xml::events::BytesStart,
+    reader: &quick_xml::Reader<&[u8]>,
+) -> anyhow::Result<String> {
+    let href = element
+        .attributes()
+        .flatten()
+        .find(|attribute| attribute.key == b"href")
+        .map(|attribute| attribute.unescape_and_decode_value(reader))
+        .transpose()?;
+
+    Ok(href.unwrap_or_default())
+}
+
+// => url title lines, with second and third level headings naming folders
+fn parse_gemtext(input: &str) -> Vec<ImportedBookmark> {
+    let mut bookmarks = vec![];
+    let mut folder = String::new();
+
+    for line in input.lines() {
+        if let Some(link) = line.strip_prefix("=>") {
+            let link = link.trim();
+            let (url, title) = link.split_once(char::is_whitespace).unwrap_or((link, ""));
+
+            bookmarks.push(ImportedBookmark::new(url, title, &folder));
+        } else if let Some(heading) = line.strip_prefix("##") {
+            folder = heading.trim_start_matches('#').trim().to_string();
+        }
+    }
+
+    bookmarks
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bookmark(url: &str, title: &str, tags: &str, folder: &str) -> ImportedBookmark {
+        ImportedBookmark {
+            url: url.to_string(),
+            title: title.to_string(),
+            tags: tags.to_string(),
+            folder: folder.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_lagrange() {
+        let input = r#"
+[1]
+title = "Reading"
+created = 1600000000
+
+[2]
+url = "gemini://example.org/"
+title = "Example \"capsule\""
+tags = "news homepage .usericon"
+parent = 1
+order = 2
+
+[3]
+url = "gemini://example.com/"
+title = "Other"
+order = 1
+"#;
+
+        assert_eq!(
+            vec![
+                bookmark("gemini://example.com/", "Other", "", ""),
+                bookmark(
+                    "gemini://example.org/",
+                    "Example \"capsule\"",
+                    "news",
+                    "Reading"
+                ),
+            ],
+            parse_lagrange(input)
+        );
+    }
+
+    #[test]
+    fn test_parse_xbel() {
+        let input = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0">
+    <bookmark href="gemini://example.org/">
+        <title>Example &amp; co</title>
+    </bookmark>
+    <folder>
+        <title>Reading</title>
+        <bookmark href="gemini://example.com/"/>
+    </folder>
+</xbel>"#;
+
+        assert_eq!(
+            vec![
+                bookmark("gemini://example.org/", "Example & co", "", ""),
+                bookmark(
+                    "gemini://example.com/",
+                    "gemini://example.com/",
+                    "",
+                    "Reading"
+                ),
+            ],
+            parse_xbel(input).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_multi_line_titles() {
+        let lagrange = r#"
+[1]
+title = "Long\nreads"
+
+[2]
+url = "gemini://example.org/"
+title = "First line\n=> gemini://example.com/ second"
+parent = 1
+"#;
+
+        let xbel = r#"<xbel version="1.0">
+    <bookmark href="gemini://example.org/">
+        <title>First line
+            # second</title>
+    </bookmark>
+</xbel>"#;
+
+        assert_eq!(
+            vec![bookmark(
+                "gemini://example.org/",
+                "First line => gemini://example.com/ second",
+                "",
+                "Long reads"
+            )],
+            parse_lagrange(lagrange)
+        );
+        assert_eq!(
+            vec![bookmark(
+                "gemini://example.org/",
+                "First line # second",
+                "",
+                ""
+            )],
+            parse_xbel(xbel).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let created = time::OffsetDateTime::UNIX_EPOCH;
+        let bookmarks = vec![
+            Bookmark {
+                id: 1,
+                url: "gemini://example.org/".to_string(),
+                title: "Example".to_string(),
+                tags: String::new(),
+                folder: String::new(),
+                position: 0,
+                created,
+            },
+            Bookmark {
+                id: 2,
+                url: "gemini://example.com/".to_string(),
+                title: "Other capsule".to_string(),
+                tags: String::new(),
+                folder: "Reading".to_string(),
+                position: 0,
+                created,
+            },
+        ];
+
+        let page = export(&bookmarks);
+
+        assert_eq!(
+            "# Bookmarks\n\n=> gemini://example.org/ Example\n\n## Reading\n\n=> gemini://example.com/ Other capsule\n",
+            page
+        );
+        assert_eq!(
+            vec![
+                bookmark("gemini://example.org/", "Example", "", ""),
+                bookmark("gemini://example.com/", "Other capsule", "", "Reading"),
+            ],
+            parse_gemtext(&page)
+        );
+    }
+}
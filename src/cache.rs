@@ -0,0 +1,316 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use log::{info, warn};
+use time::OffsetDateTime;
+use url::Url;
+
+use crate::client::GEMINI_SCHEME;
+use crate::db::Db;
+use crate::gemini::{Document, Line};
+use crate::header::{build_header, Inner, ParseMode};
+use crate::response::Response;
+use crate::tls::DEFAULT_GEMINI_PORT;
+
+pub struct ResponseCache {
+    db: Rc<Db>,
+    ttl: Duration,
+}
+
+impl fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ResponseCache")
+    }
+}
+
+impl ResponseCache {
+    pub fn new(db: Rc<Db>, ttl: Duration) -> Self {
+        Self { db, ttl }
+    }
+
+    // a response fetched within the ttl, if there is one
+    pub fn get(&self, url: &Url) -> Option<(Response, OffsetDateTime)> {
+        let (response, fetched) = self.get_any(url)?;
+
+        let age = OffsetDateTime::now_utc() - fetched;
+
+        if age.whole_seconds() > self.ttl.as_secs() as i64 {
+            info!("cached response for {} is stale", url);
+            return None;
+        }
+
+        Some((response, fetched))
+    }
+
+    // however old the copy is, used when there is no network to fall back to
+    pub fn get_any(&self, url: &Url) -> Option<(Response, OffsetDateTime)> {
+        let cached = match self.db.get_cached_response(&normalize(url)) {
+            Ok(cached) => cached?,
+            Err(e) => {
+                warn!("failed to read cached response for {}: {}", url, e);
+                return None;
+            }
+        };
+
+        // a host that now presents another certificate may not be the one that served this
+        match self.fingerprint(url) {
+            Ok(fingerprint) if fingerprint == cached.fingerprint => {}
+            Ok(_) => {
+                info!("certificate of {} changed since it was cached", url);
+                return None;
+            }
+            Err(e) => {
+                warn!("failed to check the certificate for {}: {}", url, e);
+                return None;
+            }
+        }
+
+        let (header, _) = build_header(
+            format!("{}\r\n", cached.header).as_bytes(),
+            ParseMode::Lenient,
+        )
+        .ok()?;
+
+        Some((
+            Response::new(header, Some(cached.body), url),
+            cached.fetched,
+        ))
+    }
+
+    pub fn contains(&self, url: &Url) -> bool {
+        self.db.is_cached(&normalize(url)).unwrap_or(false)
+    }
+
+    // kept until the ttl runs out
+    pub fn store(&self, response: &Response) -> anyhow::Result<bool> {
+        self.insert(response, false)
+    }
+
+    // kept however old it gets, for reading offline
+    pub fn save(&self, response: &Response) -> anyhow::Result<bool> {
+        self.insert(response, true)
+    }
+
+    // only successful, fully buffered responses from the network are worth keeping
+    fn insert(&self, response: &Response, saved: bool) -> anyhow::Result<bool> {
+        let url = response.url();
+
+        let body = match (response.header().inner(), response.body()) {
+            (Inner::Success { .. }, Some(body)) if is_remote(url) => body,
+            _ => return Ok(false),
+        };
+
+        self.db.cache_response(
+            &normalize(url),
+            &response.header().to_line(),
+            body,
+            self.fingerprint(url)?.as_deref(),
+            saved,
+        )?;
+
+        self.evict()?;
+
+        Ok(true)
+    }
+
+    fn evict(&self) -> anyhow::Result<()> {
+        let ttl = time::Duration::try_from(self.ttl)?;
+
+        self.db.evict_responses(OffsetDateTime::now_utc() - ttl)
+    }
+
+    // the certificate trusted for the host, gemini being the only scheme that has one
+    fn fingerprint(&self, url: &Url) -> anyhow::Result<Option<String>> {
+        match url.host_str() {
+            Some(host) if url.scheme() == GEMINI_SCHEME => Ok(self
+                .db
+                .get_certificate(host)?
+                .map(|certificate| certificate.fingerprint)),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NotCached(pub Url);
+
+impl fmt::Display for NotCached {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} has not been saved for offline reading", self.0)
+    }
+}
+
+impl std::error::Error for NotCached {}
+
+// local pages are cheap to load and change underneath us
+pub fn is_remote(url: &Url) -> bool {
+    url.host_str().is_some() && url.scheme() != "file"
+}
+
+// urls that fetch the same thing share a cache entry
+pub fn normalize(url: &Url) -> String {
+    let mut url = url.clone();
+
+    url.set_fragment(None);
+
+    // hosts are only lowercased by the url crate for the schemes it knows about
+    if let Some(host) = url.host_str().map(str::to_lowercase) {
+        let _ = url.set_host(Some(&host));
+    }
+
+    if url.scheme() == GEMINI_SCHEME && url.port() == Some(DEFAULT_GEMINI_PORT) {
+        let _ = url.set_port(None);
+    }
+
+    if url.path().is_empty() {
+        url.set_path("/");
+    }
+
+    url.to_string()
+}
+
+// the distinct pages a document links to on the same capsule as the document itself
+pub fn capsule_links(document: &Document, url: &Url) -> Vec<Url> {
+    let mut seen = HashSet::from([normalize(url)]);
+
+    document
+        .lines()
+        .iter()
+        .filter_map(|line| match line {
+            Line::Link { url: link, .. } => Some(link),
+            _ => None,
+        })
+        .filter(|link| {
+            link.scheme() == url.scheme()
+                && link.host_str() == url.host_str()
+                && link.port() == url.port()
+        })
+        .filter(|link| seen.insert(normalize(link)))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::header::{Header, Status};
+
+    use super::*;
+
+    fn cache(ttl: u64) -> ResponseCache {
+        let db = Rc::new(Db::new(":memory:").unwrap());
+        db.prepare().unwrap();
+
+        ResponseCache::new(db, Duration::from_secs(ttl))
+    }
+
+    fn response(url: &str, header: Header) -> Response {
+        Response::new(header, Some(b"# Hello".to_vec()), &url.parse().unwrap())
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            "gemini://example.org/",
+            normalize(&"gemini://EXAMPLE.org:1965#top".parse().unwrap())
+        );
+        assert_eq!(
+            "gemini://example.org:1966/a?b",
+            normalize(&"gemini://example.org:1966/a?b".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_store_and_get() {
+        let cache = cache(60);
+        let header = Header::success(Status::Success, "text/gemini".parse().unwrap());
+
+        assert!(cache
+            .store(&response("gemini://example.org/", header))
+            .unwrap());
+        assert!(cache.contains(&"gemini://example.org/".parse().unwrap()));
+
+        let (cached, _) = cache
+            .get(&"gemini://example.org:1965/#top".parse().unwrap())
+            .unwrap();
+
+        assert_eq!("20 text/gemini", cached.header().to_line());
+        assert_eq!(Some(&b"# Hello".to_vec()), cached.body());
+    }
+
+    #[test]
+    fn test_skips_failures() {
+        let cache = cache(60);
+
+        assert!(!cache
+            .store(&response(
+                "gemini://example.org/",
+                Header::failure(Status::NotFound, ""),
+            ))
+            .unwrap());
+
+        assert!(cache
+            .get_any(&"gemini://example.org/".parse().unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn test_capsule_links() {
+        let url: Url = "gemini://example.org/index.gmi".parse().unwrap();
+        let document = Document::new(vec![
+            Line::link("gemini://example.org/a.gmi".parse().unwrap(), None),
+            Line::link("gemini://example.org/a.gmi#top".parse().unwrap(), None),
+            Line::link("gemini://example.org/index.gmi".parse().unwrap(), None),
+            Line::link("gemini://example.com/b.gmi".parse().unwrap(), None),
+            Line::link("https://example.org/c".parse().unwrap(), None),
+        ]);
+
+        assert_eq!(
+            vec![Url::parse("gemini://example.org/a.gmi").unwrap()],
+            capsule_links(&document, &url)
+        );
+    }
+
+    #[test]
+    fn test_evicts_all_but_saved() {
+        let cache = cache(60);
+        let header = Header::success(Status::Success, "text/gemini".parse().unwrap());
+
+        cache
+            .store(&response("gemini://example.org/a.gmi", header.clone()))
+            .unwrap();
+        cache
+            .save(&response("gemini://example.org/b.gmi", header.clone()))
+            .unwrap();
+        // visiting a saved page again doesn't make it evictable
+        cache
+            .store(&response("gemini://example.org/b.gmi", header))
+            .unwrap();
+
+        cache
+            .db
+            .evict_responses(OffsetDateTime::now_utc() + time::Duration::hours(1))
+            .unwrap();
+
+        assert!(!cache.contains(&"gemini://example.org/a.gmi".parse().unwrap()));
+        assert!(cache.contains(&"gemini://example.org/b.gmi".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_skips_changed_certificate() {
+        let cache = cache(60);
+        let header = Header::success(Status::Success, "text/gemini".parse().unwrap());
+        let url = "gemini://example.org/".parse().unwrap();
+
+        cache
+            .store(&response("gemini://example.org/", header))
+            .unwrap();
+        cache
+            .db
+            .insert_certificate("example.org", "abcdef")
+            .unwrap();
+
+        assert!(cache.get_any(&url).is_none());
+    }
+}
@@ -0,0 +1,118 @@
+use encoding_rs::{Encoding, UTF_8};
+use log::warn;
+use mime::Mime;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded {
+    text: String,
+    encoding: String,
+}
+
+impl Decoded {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn encoding(&self) -> &str {
+        &self.encoding
+    }
+}
+
+// gemini defaults to utf-8 when no charset parameter is given
+pub fn decode(body: &[u8], mime: Option<&Mime>) -> Decoded {
+    let label = mime.and_then(|mime| mime.get_param(mime::CHARSET));
+
+    let (encoding, description) = match label {
+        None => (UTF_8, UTF_8.name().to_lowercase()),
+        Some(label) => match Encoding::for_label(label.as_str().as_bytes()) {
+            Some(encoding) => (encoding, encoding.name().to_lowercase()),
+            None => {
+                warn!("unknown charset {}, falling back to utf-8", label);
+
+                (UTF_8, format!("utf-8 (unknown charset: {})", label))
+            }
+        },
+    };
+
+    let (text, _, had_errors) = encoding.decode(body);
+
+    if had_errors {
+        warn!("body contained invalid {} sequences", encoding.name());
+    }
+
+    Decoded {
+        text: text.into_owned(),
+        encoding: description,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mime(s: &str) -> Mime {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_decode_default_utf8() {
+        let decoded = decode("héllo".as_bytes(), Some(&mime("text/gemini")));
+
+        assert_eq!("héllo", decoded.text());
+        assert_eq!("utf-8", decoded.encoding());
+    }
+
+    #[test]
+    fn test_decode_iso_8859_1() {
+        let decoded = decode(
+            &[0x63, 0x61, 0x66, 0xe9],
+            Some(&mime("text/plain; charset=ISO-8859-1")),
+        );
+
+        assert_eq!("café", decoded.text());
+    }
+
+    #[test]
+    fn test_decode_windows_1251() {
+        let decoded = decode(
+            &[0xcf, 0xf0, 0xe8],
+            Some(&mime("text/gemini; charset=windows-1251")),
+        );
+
+        assert_eq!("При", decoded.text());
+        assert_eq!("windows-1251", decoded.encoding());
+    }
+
+    #[test]
+    fn test_decode_koi8_r() {
+        let decoded = decode(
+            &[0xf0, 0xd2, 0xc9],
+            Some(&mime("text/gemini; charset=koi8-r")),
+        );
+
+        assert_eq!("При", decoded.text());
+    }
+
+    #[test]
+    fn test_decode_shift_jis() {
+        let decoded = decode(&[0x82, 0xa0], Some(&mime("text/gemini; charset=Shift_JIS")));
+
+        assert_eq!("あ", decoded.text());
+        assert_eq!("shift_jis", decoded.encoding());
+    }
+
+    #[test]
+    fn test_decode_unknown_charset_falls_back() {
+        let decoded = decode(b"hello", Some(&mime("text/gemini; charset=klingon")));
+
+        assert_eq!("hello", decoded.text());
+        assert_eq!("utf-8 (unknown charset: klingon)", decoded.encoding());
+    }
+
+    #[test]
+    fn test_decode_invalid_utf8_is_replaced() {
+        let decoded = decode(&[0x68, 0xff], None);
+
+        assert_eq!("h\u{fffd}", decoded.text());
+    }
+}
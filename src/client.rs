@@ -1,17 +1,28 @@
 use std::fmt;
+use std::io::Write;
 use std::rc::Rc;
-use std::{io::Read, io::Write};
 
 use log::info;
 use url::Url;
 
+use crate::about::{AboutClient, ABOUT_SCHEME};
+use crate::file::{FileClient, FILE_SCHEME};
+use crate::finger::{FingerClient, FINGER_SCHEME};
+use crate::header::ParseMode;
+use crate::nex::{NexClient, NEX_SCHEME};
 use crate::response::Response;
-use crate::tls::verification::{State, Verifier};
-use crate::tls::{build_connector, get_stream};
+use crate::titan::{TitanClient, Upload};
+use crate::tls::verification::Verifier;
+use crate::tls::{build_connector, connect};
+use crate::transfer::BodyPolicy;
+
+pub const GEMINI_SCHEME: &str = "gemini";
 
 pub struct GeminiClient {
     connector: native_tls::TlsConnector,
     verifier: Rc<dyn Verifier>,
+    header_mode: ParseMode,
+    body_policy: BodyPolicy,
 }
 
 impl fmt::Debug for GeminiClient {
@@ -21,35 +32,80 @@ impl fmt::Debug for GeminiClient {
 }
 
 impl GeminiClient {
-    pub fn new(verifier: Rc<dyn Verifier>) -> anyhow::Result<Self> {
+    pub fn new(
+        verifier: Rc<dyn Verifier>,
+        header_mode: ParseMode,
+        body_policy: BodyPolicy,
+    ) -> anyhow::Result<Self> {
         Ok(Self {
-            connector: build_connector()?,
+            connector: build_connector(None)?,
             verifier,
+            header_mode,
+            body_policy,
         })
     }
 
+    pub fn set_header_mode(&mut self, header_mode: ParseMode) {
+        self.header_mode = header_mode;
+    }
+
     pub fn get(&self, url: &Url) -> anyhow::Result<Response> {
         info!("getting url: {}", url.to_string());
 
-        let mut stream = get_stream(&self.connector, url)?;
+        let mut stream = connect(&self.connector, self.verifier.as_ref(), url)?;
 
-        let certificate = stream.peer_certificate()?;
+        stream.write_all(format!("{}\r\n", url.as_str()).as_bytes())?;
+        stream.flush()?;
 
-        let certificate_status = self.verifier.verify(certificate.as_ref(), url)?;
-        info!("TOFU certificate status: {}", certificate_status);
+        Response::read(stream, url, self.header_mode, &self.body_policy)
+    }
+}
 
-        // TODO: need to make it possible for a user to respond to this event in the UI
-        anyhow::ensure!(
-            State::Conflict != certificate_status,
-            "certificate conflict"
-        );
+#[derive(Debug)]
+pub struct Clients {
+    gemini: GeminiClient,
+    titan: TitanClient,
+    finger: FingerClient,
+    nex: NexClient,
+    file: FileClient,
+    about: AboutClient,
+}
 
-        stream.write_all(format!("{}\r\n", url.as_str()).as_bytes())?;
-        stream.flush()?;
+impl Clients {
+    pub fn new(
+        gemini: GeminiClient,
+        titan: TitanClient,
+        finger: FingerClient,
+        nex: NexClient,
+        file: FileClient,
+        about: AboutClient,
+    ) -> Self {
+        Self {
+            gemini,
+            titan,
+            finger,
+            nex,
+            file,
+            about,
+        }
+    }
 
-        let mut buf = vec![];
-        stream.read_to_end(&mut buf)?;
+    pub fn get(&self, url: &Url) -> anyhow::Result<Response> {
+        match url.scheme() {
+            FINGER_SCHEME => self.finger.get(url),
+            NEX_SCHEME => self.nex.get(url),
+            FILE_SCHEME => self.file.get(url),
+            ABOUT_SCHEME => self.about.get(url),
+            _ => self.gemini.get(url),
+        }
+    }
+
+    pub fn upload(&self, url: &Url, upload: &Upload) -> anyhow::Result<Response> {
+        self.titan.upload(url, upload)
+    }
 
-        Response::parse(&buf, url)
+    pub fn set_header_mode(&mut self, header_mode: ParseMode) {
+        self.gemini.set_header_mode(header_mode);
+        self.titan.set_header_mode(header_mode);
     }
 }
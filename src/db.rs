@@ -2,7 +2,9 @@ use anyhow::anyhow;
 use log::info;
 use rusqlite::OptionalExtension;
 
-use crate::db::model::Certificate;
+use crate::db::model::{
+    Bookmark, CachedResponse, Certificate, Download, HistoryEntry, SearchResult,
+};
 
 pub struct Db {
     connection: rusqlite::Connection,
@@ -21,7 +23,7 @@ impl Db {
         info!("preparing database");
 
         self.connection
-            .execute(
+            .execute_batch(
                 r#"
                 CREATE TABLE IF NOT EXISTS certificates (
                     id INTEGER PRIMARY KEY,
@@ -30,8 +32,68 @@ impl Db {
                     first_seen TEXT NOT NULL,
                     last_seen TEXT NOT NULL
                 );
+
+                CREATE TABLE IF NOT EXISTS downloads (
+                    id INTEGER PRIMARY KEY,
+                    url TEXT NOT NULL,
+                    path TEXT NOT NULL,
+                    mime TEXT NOT NULL,
+                    size INTEGER NOT NULL,
+                    state TEXT NOT NULL,
+                    started TEXT NOT NULL,
+                    finished TEXT
+                );
+
+                CREATE TABLE IF NOT EXISTS handler_actions (
+                    mime TEXT PRIMARY KEY,
+                    action TEXT NOT NULL
+                );
+
+                CREATE TABLE IF NOT EXISTS history (
+                    id INTEGER PRIMARY KEY,
+                    url TEXT NOT NULL,
+                    host TEXT NOT NULL,
+                    title TEXT,
+                    visited TEXT NOT NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS history_url ON history (url);
+
+                CREATE INDEX IF NOT EXISTS history_visited ON history (visited);
+
+                CREATE TABLE IF NOT EXISTS bookmarks (
+                    id INTEGER PRIMARY KEY,
+                    url TEXT NOT NULL UNIQUE,
+                    title TEXT NOT NULL,
+                    tags TEXT NOT NULL,
+                    folder TEXT NOT NULL,
+                    position INTEGER NOT NULL,
+                    created TEXT NOT NULL
+                );
+
+                CREATE TABLE IF NOT EXISTS indexed_pages (
+                    id INTEGER PRIMARY KEY,
+                    url TEXT NOT NULL UNIQUE,
+                    host TEXT NOT NULL,
+                    indexed TEXT NOT NULL
+                );
+
+                CREATE VIRTUAL TABLE IF NOT EXISTS page_text USING fts5 (
+                    title,
+                    content
+                );
+
+                CREATE TABLE IF NOT EXISTS responses (
+                    url TEXT PRIMARY KEY,
+                    header TEXT NOT NULL,
+                    body BLOB NOT NULL,
+                    fetched TEXT NOT NULL,
+                    fingerprint TEXT,
+                    saved INTEGER NOT NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS responses_fetched ON responses (fetched);
             "#,
-                [],
             )
             .map_err(|_| anyhow!("failed to prepare database"))?;
 
@@ -61,6 +123,29 @@ impl Db {
             .map_err(|_| anyhow!("error retrieving certificate from database"))
     }
 
+    pub fn get_certificates(&self) -> anyhow::Result<Vec<model::Certificate>> {
+        info!("getting all certificates");
+
+        self.connection
+            .prepare(
+                r#"
+            SELECT
+                id,
+                hostname,
+                fingerprint,
+                first_seen,
+                last_seen
+            FROM
+                certificates
+            ORDER BY
+                hostname;
+            "#,
+            )?
+            .query_map([], |row| row.try_into())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| anyhow!("error retrieving certificates from database"))
+    }
+
     pub fn insert_certificate(
         &self,
         hostname: &str,
@@ -129,7 +214,648 @@ impl Db {
     }
 }
 
-mod model {
+impl Db {
+    pub fn insert_download(&self, url: &str, path: &str, mime: &str) -> anyhow::Result<Download> {
+        info!("inserting download of {}", url);
+
+        let now = time::OffsetDateTime::now_utc();
+        let state = "running";
+
+        self.connection
+            .execute(
+                r#"
+            INSERT INTO
+                downloads (
+                    url,
+                    path,
+                    mime,
+                    size,
+                    state,
+                    started
+                )
+            VALUES (
+                ?1,
+                ?2,
+                ?3,
+                0,
+                ?4,
+                ?5
+            );
+            "#,
+                rusqlite::params![url, path, mime, state, now],
+            )
+            .map_err(|_| anyhow!("failed to insert download into database"))?;
+
+        Ok(Download {
+            id: self.connection.last_insert_rowid(),
+            url: url.to_string(),
+            path: path.to_string(),
+            mime: mime.to_string(),
+            size: 0,
+            state: state.to_string(),
+            started: now,
+            finished: None,
+        })
+    }
+
+    pub fn finish_download(&self, id: i64, size: u64, state: &str) -> anyhow::Result<()> {
+        info!("finishing download {} with state: {}", id, state);
+
+        let now = time::OffsetDateTime::now_utc();
+
+        self.connection
+            .execute(
+                r#"
+            UPDATE
+                downloads
+            SET
+                size = ?1,
+                state = ?2,
+                finished = ?3
+            WHERE
+                id = ?4;
+            "#,
+                rusqlite::params![size, state, now, id],
+            )
+            .map_err(|_| anyhow!("failed to update download"))?;
+
+        Ok(())
+    }
+
+    pub fn get_downloads(&self) -> anyhow::Result<Vec<Download>> {
+        info!("getting all downloads");
+
+        self.connection
+            .prepare(
+                r#"
+            SELECT
+                id,
+                url,
+                path,
+                mime,
+                size,
+                state,
+                started,
+                finished
+            FROM
+                downloads
+            ORDER BY
+                started DESC;
+            "#,
+            )?
+            .query_map([], |row| row.try_into())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| anyhow!("error retrieving downloads from database"))
+    }
+
+    // downloads still running when the browser quit or crashed will never finish
+    pub fn interrupt_downloads(&self) -> anyhow::Result<()> {
+        info!("marking unfinished downloads as interrupted");
+
+        self.connection
+            .execute(
+                r#"
+            UPDATE
+                downloads
+            SET
+                state = 'failed: interrupted'
+            WHERE
+                state = 'running';
+            "#,
+                [],
+            )
+            .map_err(|_| anyhow!("failed to mark downloads as interrupted"))?;
+
+        Ok(())
+    }
+
+    pub fn clear_downloads(&self) -> anyhow::Result<()> {
+        info!("clearing finished downloads");
+
+        self.connection
+            .execute("DELETE FROM downloads WHERE state != 'running';", [])
+            .map_err(|_| anyhow!("failed to clear downloads"))?;
+
+        Ok(())
+    }
+}
+
+impl Db {
+    pub fn get_handler_action(&self, mime: &str) -> anyhow::Result<Option<String>> {
+        info!("getting handler action for: {}", mime);
+
+        self.connection
+            .prepare(
+                r#"
+            SELECT
+                action
+            FROM
+                handler_actions
+            WHERE
+                mime = ?1;
+            "#,
+            )?
+            .query_row(rusqlite::params![mime], |row| row.get(0))
+            .optional()
+            .map_err(|_| anyhow!("error retrieving handler action from database"))
+    }
+
+    pub fn set_handler_action(&self, mime: &str, action: &str) -> anyhow::Result<()> {
+        info!("setting handler action for {} to {}", mime, action);
+
+        self.connection
+            .execute(
+                r#"
+            INSERT OR REPLACE INTO
+                handler_actions (
+                    mime,
+                    action
+                )
+            VALUES (
+                ?1,
+                ?2
+            );
+            "#,
+                rusqlite::params![mime, action],
+            )
+            .map_err(|_| anyhow!("failed to set handler action"))?;
+
+        Ok(())
+    }
+}
+
+impl Db {
+    pub fn get_cached_response(&self, url: &str) -> anyhow::Result<Option<CachedResponse>> {
+        info!("getting cached response for: {}", url);
+
+        self.connection
+            .prepare(
+                r#"
+            SELECT
+                url,
+                header,
+                body,
+                fetched,
+                fingerprint
+            FROM
+                responses
+            WHERE
+                url = ?1;
+            "#,
+            )?
+            .query_row(rusqlite::params![url], |row| row.try_into())
+            .optional()
+            .map_err(|_| anyhow!("error retrieving cached response from database"))
+    }
+
+    pub fn is_cached(&self, url: &str) -> anyhow::Result<bool> {
+        self.connection
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM responses WHERE url = ?1);",
+                rusqlite::params![url],
+                |row| row.get(0),
+            )
+            .map_err(|_| anyhow!("error checking response cache"))
+    }
+
+    pub fn cache_response(
+        &self,
+        url: &str,
+        header: &str,
+        body: &[u8],
+        fingerprint: Option<&str>,
+        saved: bool,
+    ) -> anyhow::Result<()> {
+        info!("caching response for {}", url);
+
+        let now = time::OffsetDateTime::now_utc();
+
+        // a page saved for offline reading stays saved when it is visited again
+        self.connection
+            .execute(
+                r#"
+            INSERT INTO
+                responses (
+                    url,
+                    header,
+                    body,
+                    fetched,
+                    fingerprint,
+                    saved
+                )
+            VALUES (
+                ?1,
+                ?2,
+                ?3,
+                ?4,
+                ?5,
+                ?6
+            )
+            ON CONFLICT (url) DO UPDATE SET
+                header = excluded.header,
+                body = excluded.body,
+                fetched = excluded.fetched,
+                fingerprint = excluded.fingerprint,
+                saved = saved OR excluded.saved;
+            "#,
+                rusqlite::params![url, header, body, now, fingerprint, saved],
+            )
+            .map_err(|_| anyhow!("failed to cache response"))?;
+
+        Ok(())
+    }
+
+    // responses saved for offline reading are kept however old they are
+    pub fn evict_responses(&self, fetched_before: time::OffsetDateTime) -> anyhow::Result<()> {
+        info!(
+            "evicting cached responses fetched before {}",
+            fetched_before
+        );
+
+        self.connection
+            .execute(
+                "DELETE FROM responses WHERE saved = 0 AND fetched < ?1;",
+                rusqlite::params![fetched_before],
+            )
+            .map_err(|_| anyhow!("failed to evict cached responses"))?;
+
+        Ok(())
+    }
+}
+
+impl Db {
+    // pages visited without a title keep the one they were last seen with
+    pub fn record_visit(
+        &self,
+        url: &str,
+        host: &str,
+        title: Option<&str>,
+        visited: time::OffsetDateTime,
+    ) -> anyhow::Result<()> {
+        info!("recording visit to {}", url);
+
+        self.connection
+            .execute(
+                r#"
+            INSERT INTO
+                history (
+                    url,
+                    host,
+                    title,
+                    visited
+                )
+            VALUES (
+                ?1,
+                ?2,
+                COALESCE(
+                    ?3,
+                    (SELECT title FROM history WHERE url = ?1 ORDER BY visited DESC LIMIT 1)
+                ),
+                ?4
+            );
+            "#,
+                rusqlite::params![url, host, title, visited],
+            )
+            .map_err(|_| anyhow!("failed to record visit"))?;
+
+        Ok(())
+    }
+
+    // one entry per page with its latest visit, an empty query matches everything
+    pub fn search_history(&self, query: &str, limit: u32) -> anyhow::Result<Vec<HistoryEntry>> {
+        info!("searching history for: {}", query);
+
+        self.connection
+            .prepare(
+                r#"
+            SELECT
+                url,
+                host,
+                title,
+                MAX(visited) AS last_visited,
+                COUNT(*)
+            FROM
+                history
+            GROUP BY
+                url
+            HAVING
+                instr(lower(url), lower(?1)) > 0
+                OR instr(lower(title), lower(?1)) > 0
+            ORDER BY
+                last_visited DESC
+            LIMIT
+                ?2;
+            "#,
+            )?
+            .query_map(rusqlite::params![query, limit], |row| row.try_into())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| anyhow!("error retrieving history from database"))
+    }
+
+    // pages indexed in the range go too, even when they were also visited before it
+    pub fn clear_history(&self, since: time::OffsetDateTime) -> anyhow::Result<()> {
+        info!("clearing history since {}", since);
+
+        let transaction = self.connection.unchecked_transaction()?;
+
+        transaction
+            .execute(
+                "DELETE FROM history WHERE visited >= ?1;",
+                rusqlite::params![since],
+            )
+            .map_err(|_| anyhow!("failed to clear history"))?;
+
+        transaction
+            .execute(
+                r#"
+            DELETE FROM
+                page_text
+            WHERE
+                rowid IN (SELECT id FROM indexed_pages WHERE indexed >= ?1);
+            "#,
+                rusqlite::params![since],
+            )
+            .map_err(|_| anyhow!("failed to remove pages from index"))?;
+
+        transaction
+            .execute(
+                "DELETE FROM indexed_pages WHERE indexed >= ?1;",
+                rusqlite::params![since],
+            )
+            .map_err(|_| anyhow!("failed to remove pages from index"))?;
+
+        transaction
+            .commit()
+            .map_err(|_| anyhow!("failed to clear history"))
+    }
+
+    pub fn delete_history_host(&self, host: &str) -> anyhow::Result<()> {
+        info!("deleting history for {}", host);
+
+        let transaction = self.connection.unchecked_transaction()?;
+
+        transaction
+            .execute(
+                "DELETE FROM history WHERE host = ?1;",
+                rusqlite::params![host],
+            )
+            .map_err(|_| anyhow!("failed to delete history"))?;
+
+        transaction
+            .execute(
+                r#"
+            DELETE FROM
+                page_text
+            WHERE
+                rowid IN (SELECT id FROM indexed_pages WHERE host = ?1);
+            "#,
+                rusqlite::params![host],
+            )
+            .map_err(|_| anyhow!("failed to remove pages from index"))?;
+
+        transaction
+            .execute(
+                "DELETE FROM indexed_pages WHERE host = ?1;",
+                rusqlite::params![host],
+            )
+            .map_err(|_| anyhow!("failed to remove pages from index"))?;
+
+        transaction
+            .commit()
+            .map_err(|_| anyhow!("failed to delete history"))
+    }
+}
+
+impl Db {
+    pub fn get_bookmarks(&self) -> anyhow::Result<Vec<Bookmark>> {
+        info!("getting all bookmarks");
+
+        self.connection
+            .prepare(
+                r#"
+            SELECT
+                id,
+                url,
+                title,
+                tags,
+                folder,
+                position,
+                created
+            FROM
+                bookmarks
+            ORDER BY
+                folder,
+                position;
+            "#,
+            )?
+            .query_map([], |row| row.try_into())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| anyhow!("error retrieving bookmarks from database"))
+    }
+
+    // new bookmarks go to the end of their folder
+    pub fn insert_bookmark(
+        &self,
+        url: &str,
+        title: &str,
+        tags: &str,
+        folder: &str,
+    ) -> anyhow::Result<()> {
+        info!("inserting bookmark for {}", url);
+
+        let now = time::OffsetDateTime::now_utc();
+
+        self.connection
+            .execute(
+                r#"
+            INSERT INTO
+                bookmarks (
+                    url,
+                    title,
+                    tags,
+                    folder,
+                    position,
+                    created
+                )
+            VALUES (
+                ?1,
+                ?2,
+                ?3,
+                ?4,
+                (SELECT COALESCE(MAX(position) + 1, 0) FROM bookmarks WHERE folder = ?4),
+                ?5
+            );
+            "#,
+                rusqlite::params![url, title, tags, folder, now],
+            )
+            .map_err(|_| anyhow!("failed to insert bookmark into database"))?;
+
+        Ok(())
+    }
+
+    pub fn update_bookmark(
+        &self,
+        id: i64,
+        title: &str,
+        tags: &str,
+        folder: &str,
+    ) -> anyhow::Result<()> {
+        info!("updating bookmark {}", id);
+
+        self.connection
+            .execute(
+                r#"
+            UPDATE
+                bookmarks
+            SET
+                title = ?1,
+                tags = ?2,
+                folder = ?3
+            WHERE
+                id = ?4;
+            "#,
+                rusqlite::params![title, tags, folder, id],
+            )
+            .map_err(|_| anyhow!("failed to update bookmark"))?;
+
+        Ok(())
+    }
+
+    // stores the order of the given bookmarks, along with the folder each one was dropped into
+    pub fn move_bookmarks(&self, bookmarks: &[Bookmark]) -> anyhow::Result<()> {
+        info!("reordering {} bookmarks", bookmarks.len());
+
+        let transaction = self.connection.unchecked_transaction()?;
+
+        for (position, bookmark) in bookmarks.iter().enumerate() {
+            transaction
+                .execute(
+                    "UPDATE bookmarks SET folder = ?1, position = ?2 WHERE id = ?3;",
+                    rusqlite::params![bookmark.folder, position as i64, bookmark.id],
+                )
+                .map_err(|_| anyhow!("failed to move bookmark"))?;
+        }
+
+        transaction
+            .commit()
+            .map_err(|_| anyhow!("failed to reorder bookmarks"))
+    }
+
+    pub fn delete_bookmark(&self, id: i64) -> anyhow::Result<()> {
+        info!("deleting bookmark {}", id);
+
+        self.connection
+            .execute(
+                "DELETE FROM bookmarks WHERE id = ?1;",
+                rusqlite::params![id],
+            )
+            .map_err(|_| anyhow!("failed to delete bookmark"))?;
+
+        Ok(())
+    }
+}
+
+impl Db {
+    // a page is indexed once, revisiting it replaces the old text under the same rowid
+    pub fn index_page(
+        &self,
+        url: &str,
+        host: &str,
+        title: &str,
+        content: &str,
+    ) -> anyhow::Result<()> {
+        info!("indexing page: {}", url);
+
+        let now = time::OffsetDateTime::now_utc();
+
+        let transaction = self.connection.unchecked_transaction()?;
+
+        let id: i64 = transaction
+            .query_row(
+                r#"
+            INSERT INTO
+                indexed_pages (
+                    url,
+                    host,
+                    indexed
+                )
+            VALUES (
+                ?1,
+                ?2,
+                ?3
+            )
+            ON CONFLICT (url) DO UPDATE SET
+                host = excluded.host,
+                indexed = excluded.indexed
+            RETURNING
+                id;
+            "#,
+                rusqlite::params![url, host, now],
+                |row| row.get(0),
+            )
+            .map_err(|_| anyhow!("failed to add page to index"))?;
+
+        transaction
+            .execute(
+                "DELETE FROM page_text WHERE rowid = ?1;",
+                rusqlite::params![id],
+            )
+            .map_err(|_| anyhow!("failed to remove page from index"))?;
+
+        transaction
+            .execute(
+                r#"
+            INSERT INTO
+                page_text (
+                    rowid,
+                    title,
+                    content
+                )
+            VALUES (
+                ?1,
+                ?2,
+                ?3
+            );
+            "#,
+                rusqlite::params![id, title, content],
+            )
+            .map_err(|_| anyhow!("failed to add page to index"))?;
+
+        transaction
+            .commit()
+            .map_err(|_| anyhow!("failed to index page"))
+    }
+
+    // the query uses fts5 syntax, best matches come first
+    pub fn search_pages(&self, query: &str, limit: u32) -> anyhow::Result<Vec<SearchResult>> {
+        info!("searching pages for: {}", query);
+
+        self.connection
+            .prepare(
+                r#"
+            SELECT
+                indexed_pages.url,
+                page_text.title,
+                snippet(page_text, 1, '', '', '...', 16)
+            FROM
+                page_text
+                JOIN indexed_pages ON indexed_pages.id = page_text.rowid
+            WHERE
+                page_text MATCH ?1
+            ORDER BY
+                rank
+            LIMIT
+                ?2;
+            "#,
+            )?
+            .query_map(rusqlite::params![query, limit], |row| row.try_into())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| anyhow!("error searching pages"))
+    }
+}
+
+pub mod model {
+    #[allow(dead_code)]
     pub struct Certificate {
         pub id: i64,
         pub hostname: String,
@@ -151,4 +877,140 @@ mod model {
             })
         }
     }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone)]
+    pub struct Download {
+        pub id: i64,
+        pub url: String,
+        pub path: String,
+        pub mime: String,
+        pub size: u64,
+        pub state: String,
+        pub started: time::OffsetDateTime,
+        pub finished: Option<time::OffsetDateTime>,
+    }
+
+    impl TryFrom<&rusqlite::Row<'_>> for Download {
+        type Error = rusqlite::Error;
+
+        fn try_from(row: &rusqlite::Row) -> Result<Self, Self::Error> {
+            Ok(Self {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                path: row.get(2)?,
+                mime: row.get(3)?,
+                size: row.get(4)?,
+                state: row.get(5)?,
+                started: row.get(6)?,
+                finished: row.get(7)?,
+            })
+        }
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone)]
+    pub struct CachedResponse {
+        pub url: String,
+        pub header: String,
+        pub body: Vec<u8>,
+        pub fetched: time::OffsetDateTime,
+        pub fingerprint: Option<String>,
+    }
+
+    impl TryFrom<&rusqlite::Row<'_>> for CachedResponse {
+        type Error = rusqlite::Error;
+
+        fn try_from(row: &rusqlite::Row) -> Result<Self, Self::Error> {
+            Ok(Self {
+                url: row.get(0)?,
+                header: row.get(1)?,
+                body: row.get(2)?,
+                fetched: row.get(3)?,
+                fingerprint: row.get(4)?,
+            })
+        }
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone)]
+    pub struct HistoryEntry {
+        pub url: String,
+        pub host: String,
+        pub title: Option<String>,
+        pub visited: time::OffsetDateTime,
+        pub visits: u64,
+    }
+
+    impl TryFrom<&rusqlite::Row<'_>> for HistoryEntry {
+        type Error = rusqlite::Error;
+
+        fn try_from(row: &rusqlite::Row) -> Result<Self, Self::Error> {
+            Ok(Self {
+                url: row.get(0)?,
+                host: row.get(1)?,
+                title: row.get(2)?,
+                visited: row.get(3)?,
+                visits: row.get(4)?,
+            })
+        }
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone)]
+    pub struct Bookmark {
+        pub id: i64,
+        pub url: String,
+        pub title: String,
+        pub tags: String,
+        pub folder: String,
+        pub position: i64,
+        pub created: time::OffsetDateTime,
+    }
+
+    impl Bookmark {
+        // tags are stored as a comma separated list
+        pub fn tags(&self) -> Vec<&str> {
+            self.tags
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .collect()
+        }
+    }
+
+    impl TryFrom<&rusqlite::Row<'_>> for Bookmark {
+        type Error = rusqlite::Error;
+
+        fn try_from(row: &rusqlite::Row) -> Result<Self, Self::Error> {
+            Ok(Self {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                title: row.get(2)?,
+                tags: row.get(3)?,
+                folder: row.get(4)?,
+                position: row.get(5)?,
+                created: row.get(6)?,
+            })
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct SearchResult {
+        pub url: String,
+        pub title: String,
+        pub snippet: String,
+    }
+
+    impl TryFrom<&rusqlite::Row<'_>> for SearchResult {
+        type Error = rusqlite::Error;
+
+        fn try_from(row: &rusqlite::Row) -> Result<Self, Self::Error> {
+            Ok(Self {
+                url: row.get(0)?,
+                title: row.get(1)?,
+                snippet: row.get(2)?,
+            })
+        }
+    }
 }
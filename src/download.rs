@@ -0,0 +1,170 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use log::info;
+use mime::Mime;
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+use crate::mime_types;
+
+// the last path segment is the best name most capsules give us
+pub fn infer_file_name(url: &Url, mime: Option<&Mime>) -> String {
+    let segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+        .map(|segment| percent_decode_str(segment).decode_utf8_lossy().to_string());
+
+    let mut file_name = segment
+        .or_else(|| url.host_str().map(str::to_string))
+        .map(|name| sanitize(&name))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "download".to_string());
+
+    if Path::new(&file_name).extension().is_none() {
+        if let Some(extension) = mime.and_then(mime_types::extension) {
+            file_name = format!("{}.{}", file_name, extension);
+        }
+    }
+
+    file_name
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '\0' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim_start_matches('.')
+        .trim()
+        .to_string()
+}
+
+// never overwrite an earlier download, append a counter the way most browsers do. the file
+// is created right away, so two downloads with the same name can't both claim one path
+pub fn create_unique_file(directory: &Path, file_name: &str) -> io::Result<(PathBuf, File)> {
+    fs::create_dir_all(directory)?;
+
+    let name = Path::new(file_name);
+    let stem = name
+        .file_stem()
+        .map_or_else(|| file_name.into(), |s| s.to_string_lossy());
+    let extension = name.extension().map(|e| e.to_string_lossy());
+
+    let mut path = directory.join(file_name);
+    let mut n = 0;
+
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                n += 1;
+                path = match &extension {
+                    Some(extension) => directory.join(format!("{} ({}).{}", stem, n, extension)),
+                    None => directory.join(format!("{} ({})", stem, n)),
+                };
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+pub fn default_open_command() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    }
+}
+
+pub fn open_containing_folder(command: &str, path: &Path) -> anyhow::Result<()> {
+    let folder = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+
+    spawn_command(command, folder)
+}
+
+// commands are split on whitespace and run without a shell, mailcap style: %s is replaced
+// by the path, which is appended when there is no placeholder
+pub fn spawn_command(command: &str, path: &Path) -> anyhow::Result<()> {
+    let mut parts = command.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("no command configured"))?;
+
+    let mut args: Vec<OsString> = parts.map(OsString::from).collect();
+
+    match args.iter().position(|arg| arg == "%s") {
+        Some(index) => args[index] = path.as_os_str().to_owned(),
+        None => args.push(path.as_os_str().to_owned()),
+    }
+
+    info!("running {} with: {:?}", program, args);
+
+    Command::new(program).args(args).spawn()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn name(url: &str, mime: Option<&str>) -> String {
+        let mime: Option<Mime> = mime.map(|m| m.parse().unwrap());
+
+        infer_file_name(&url.parse().unwrap(), mime.as_ref())
+    }
+
+    #[test]
+    fn test_infer_file_name() {
+        assert_eq!(
+            "paper.pdf",
+            name("gemini://example.org/docs/paper.pdf", None)
+        );
+        assert_eq!(
+            "my notes.txt",
+            name("gemini://example.org/my%20notes.txt", None)
+        );
+        assert_eq!(
+            "photos.png",
+            name("gemini://example.org/photos/", Some("image/png"))
+        );
+        assert_eq!("example.org", name("gemini://example.org/", None));
+        assert_eq!(
+            "_.._passwd",
+            name("gemini://example.org/..%2F..%2Fpasswd", None)
+        );
+    }
+
+    #[test]
+    fn test_create_unique_file() {
+        let directory = std::env::temp_dir()
+            .join(format!("dioscuri-test-{}", std::process::id()))
+            .join("unique");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("cat.png"), b"").unwrap();
+        fs::write(directory.join("cat (1).png"), b"").unwrap();
+
+        let (first, _) = create_unique_file(&directory, "cat.png").unwrap();
+        let (second, _) = create_unique_file(&directory, "cat.png").unwrap();
+
+        assert_eq!(directory.join("cat (2).png"), first);
+        assert_eq!(directory.join("cat (3).png"), second);
+        assert_eq!(
+            directory.join("dog"),
+            create_unique_file(&directory, "dog").unwrap().0
+        );
+    }
+}
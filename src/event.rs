@@ -1,5 +1,8 @@
 use crossbeam::channel::{unbounded, Receiver, Sender};
 
+use crate::header::ParseMode;
+use crate::titan::Upload;
+
 pub type EventSender = Sender<Event>;
 pub type EventReceiver = Receiver<Event>;
 
@@ -10,11 +13,37 @@ pub type EventRelay = Receiver<Event>;
 pub enum Event {
     Back,
     Forward,
-    Load { url: String, add_to_session: bool },
+    Load {
+        url: String,
+        add_to_session: bool,
+        from_cache: bool,
+    },
+    Retry {
+        url: String,
+        attempt: u32,
+    },
+    LoadImage {
+        url: String,
+    },
     Home,
     Quit,
     Stop,
     Refresh,
+    ShowUpload,
+    ShowPageInfo,
+    ShowDownloads,
+    ShowHistory,
+    ShowBookmarks,
+    ShowSearch,
+    ToggleBookmark,
+    Upload {
+        url: String,
+        upload: Upload,
+    },
+    SetHeaderMode(ParseMode),
+    SetInlineImages(bool),
+    SetOffline(bool),
+    SaveForOffline,
 }
 
 impl Event {
@@ -30,6 +59,7 @@ impl Event {
         Self::Load {
             url: url.to_string(),
             add_to_session: true,
+            from_cache: false,
         }
     }
 
@@ -37,6 +67,30 @@ impl Event {
         Self::Load {
             url: url.to_string(),
             add_to_session: false,
+            from_cache: false,
+        }
+    }
+
+    // back and forward revisit pages, so a recent copy is good enough
+    pub fn load_from_cache(url: &str) -> Self {
+        Self::Load {
+            url: url.to_string(),
+            add_to_session: false,
+            from_cache: true,
+        }
+    }
+
+    // reloads a page that failed, counting how often it was tried already
+    pub fn retry(url: &str, attempt: u32) -> Self {
+        Self::Retry {
+            url: url.to_string(),
+            attempt,
+        }
+    }
+
+    pub fn load_image(url: &str) -> Self {
+        Self::LoadImage {
+            url: url.to_string(),
         }
     }
 
@@ -55,6 +109,57 @@ impl Event {
     pub fn refresh() -> Self {
         Self::Refresh
     }
+
+    pub fn show_upload() -> Self {
+        Self::ShowUpload
+    }
+
+    pub fn set_header_mode(header_mode: ParseMode) -> Self {
+        Self::SetHeaderMode(header_mode)
+    }
+
+    pub fn set_inline_images(inline_images: bool) -> Self {
+        Self::SetInlineImages(inline_images)
+    }
+
+    pub fn set_offline(offline: bool) -> Self {
+        Self::SetOffline(offline)
+    }
+
+    pub fn save_for_offline() -> Self {
+        Self::SaveForOffline
+    }
+
+    pub fn show_page_info() -> Self {
+        Self::ShowPageInfo
+    }
+
+    pub fn show_downloads() -> Self {
+        Self::ShowDownloads
+    }
+
+    pub fn show_history() -> Self {
+        Self::ShowHistory
+    }
+
+    pub fn show_bookmarks() -> Self {
+        Self::ShowBookmarks
+    }
+
+    pub fn show_search() -> Self {
+        Self::ShowSearch
+    }
+
+    pub fn toggle_bookmark() -> Self {
+        Self::ToggleBookmark
+    }
+
+    pub fn upload(url: &str, upload: Upload) -> Self {
+        Self::Upload {
+            url: url.to_string(),
+            upload,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -95,6 +200,10 @@ impl EventBus {
         Ok(())
     }
 
+    pub fn has_pending(&self) -> bool {
+        !self.event_relay.is_empty()
+    }
+
     fn send_broadcast(&self, event: Event) -> anyhow::Result<()> {
         for channel in &self.channels {
             channel.send(event.clone())?;
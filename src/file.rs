@@ -0,0 +1,202 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::anyhow;
+use log::info;
+use url::Url;
+
+use crate::header::{Header, Status};
+use crate::mime_types;
+use crate::response::Response;
+use crate::transfer::{read_body, Body, BodyPolicy, Transfer};
+
+pub const FILE_SCHEME: &str = "file";
+
+#[derive(Debug)]
+pub struct FileClient {
+    body_policy: BodyPolicy,
+}
+
+impl FileClient {
+    pub fn new(body_policy: BodyPolicy) -> Self {
+        Self { body_policy }
+    }
+
+    pub fn get(&self, url: &Url) -> anyhow::Result<Response> {
+        info!("reading file url: {}", url.to_string());
+
+        let path = url
+            .to_file_path()
+            .map_err(|_| anyhow!("could not convert url to a local path: {}", url))?;
+
+        if path.is_dir() {
+            let url = directory_url(url);
+            let body = build_directory_listing(&path, &url)?;
+
+            let header = Header::success(Status::Success, mime_types::gemini());
+
+            return Ok(Response::new(header, Some(body.into_bytes()), &url));
+        }
+
+        let mime = mime_types::from_path(url.path());
+
+        // files larger than a document are streamed to the download directory like any other
+        let body = if fs::metadata(&path)?.len() <= self.body_policy.max_document_size() {
+            read_body(fs::File::open(&path)?, url, &mime, &self.body_policy)?
+        } else {
+            let (download_path, file) = self.body_policy.create_download_file(url, &mime)?;
+
+            Body::Streamed(Transfer::spawn(
+                url,
+                &download_path,
+                file,
+                vec![],
+                fs::File::open(&path)?,
+            )?)
+        };
+
+        let header = Header::success(Status::Success, mime);
+
+        Ok(Response::from_body(header, body, url))
+    }
+}
+
+// relative links in a page resolve against the directory holding it
+pub fn base_url(url: &Url) -> Url {
+    url.join("./").unwrap_or_else(|_| url.clone())
+}
+
+fn directory_url(url: &Url) -> Url {
+    let mut url = url.clone();
+
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+
+    url
+}
+
+fn build_directory_listing(path: &Path, url: &Url) -> anyhow::Result<String> {
+    let mut entries = fs::read_dir(path)?
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let mut name = entry.file_name().to_string_lossy().to_string();
+            let is_dir = entry.path().is_dir();
+
+            if is_dir {
+                name.push('/');
+            }
+
+            (name, entry.path(), is_dir)
+        })
+        .collect::<Vec<_>>();
+
+    // directories first, then files, both alphabetically
+    entries.sort_by_key(|(name, _, _)| (!name.ends_with('/'), name.to_lowercase()));
+
+    let mut listing = format!("# Index of {}\n\n", path.display());
+
+    if path.parent().is_some() {
+        listing.push_str(&format!("=> {} ..\n", url.join("../")?));
+    }
+
+    // links come from the paths themselves, names with '#', '?' or ':' are not relative urls
+    for (name, path, is_dir) in entries {
+        let link = if is_dir {
+            Url::from_directory_path(&path)
+        } else {
+            Url::from_file_path(&path)
+        }
+        .map_err(|_| anyhow!("could not build a url for {}", path.display()))?;
+
+        listing.push_str(&format!("=> {} {}\n", link, name));
+    }
+
+    Ok(listing)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_base_url() {
+        let url = Url::parse("file:///capsule/notes/index.gmi").unwrap();
+
+        assert_eq!("file:///capsule/notes/", base_url(&url).as_str());
+    }
+
+    #[test]
+    fn test_directory_url() {
+        let url = Url::parse("file:///capsule/notes").unwrap();
+
+        assert_eq!("file:///capsule/notes/", directory_url(&url).as_str());
+    }
+
+    #[test]
+    fn test_build_directory_listing() {
+        let root = std::env::temp_dir().join("dioscuri_test_build_directory_listing");
+        let _ = fs::remove_dir_all(&root);
+
+        fs::create_dir_all(root.join("posts")).unwrap();
+        fs::write(root.join("index.gmi"), "# Hello").unwrap();
+        fs::write(root.join("about me.gmi"), "# About").unwrap();
+
+        let url = directory_url(&Url::from_directory_path(&root).unwrap());
+        let listing = build_directory_listing(&root, &url).unwrap();
+
+        let links = listing
+            .lines()
+            .filter(|line| line.starts_with("=>"))
+            .skip(1)
+            .collect::<Vec<&str>>();
+
+        assert_eq!(
+            vec![
+                format!("=> {}posts/ posts/", url),
+                format!("=> {}about%20me.gmi about me.gmi", url),
+                format!("=> {}index.gmi index.gmi", url),
+            ],
+            links
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_build_directory_listing_special_names() {
+        let root = std::env::temp_dir().join("dioscuri_test_build_directory_listing_special_names");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let names = ["#notes.gmi", "a:b", "what?.gmi", "100%.gmi"];
+
+        for name in names {
+            fs::write(root.join(name), "").unwrap();
+        }
+
+        let url = directory_url(&Url::from_directory_path(&root).unwrap());
+        let listing = build_directory_listing(&root, &url).unwrap();
+
+        let mut paths = listing
+            .lines()
+            .filter_map(|line| line.strip_prefix("=> "))
+            .skip(1)
+            .map(|link| {
+                let url = Url::parse(link.split_once(' ').unwrap().0).unwrap();
+
+                assert_eq!(FILE_SCHEME, url.scheme());
+
+                url.to_file_path().unwrap()
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let mut expected = names.map(|name| root.join(name)).to_vec();
+        expected.sort();
+
+        assert_eq!(expected, paths);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
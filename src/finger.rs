@@ -0,0 +1,75 @@
+use std::io::Write;
+
+use log::info;
+use url::Url;
+
+use crate::header::{Header, Status};
+use crate::net::get_tcp_stream;
+use crate::response::Response;
+use crate::transfer::{read_body, BodyPolicy};
+
+pub const FINGER_SCHEME: &str = "finger";
+
+const DEFAULT_FINGER_PORT: u16 = 79;
+
+#[derive(Debug)]
+pub struct FingerClient {
+    body_policy: BodyPolicy,
+}
+
+impl FingerClient {
+    pub fn new(body_policy: BodyPolicy) -> Self {
+        Self { body_policy }
+    }
+
+    pub fn get(&self, url: &Url) -> anyhow::Result<Response> {
+        info!("fingering url: {}", url.to_string());
+
+        let mut stream = get_tcp_stream(url, DEFAULT_FINGER_PORT)?;
+
+        stream.write_all(format!("{}\r\n", build_query(url)).as_bytes())?;
+        stream.flush()?;
+
+        let mime = mime::TEXT_PLAIN_UTF_8;
+
+        let body = read_body(stream, url, &mime, &self.body_policy)?;
+        let header = Header::success(Status::Success, mime);
+
+        Ok(Response::from_body(header, body, url))
+    }
+}
+
+// both finger://user@host and finger://host/user are in the wild
+fn build_query(url: &Url) -> String {
+    if !url.username().is_empty() {
+        return url.username().to_string();
+    }
+
+    url.path().trim_start_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_build_query_user_at_host() {
+        let url = Url::parse("finger://trevor@example.org").unwrap();
+
+        assert_eq!("trevor", build_query(&url));
+    }
+
+    #[test]
+    fn test_build_query_path() {
+        let url = Url::parse("finger://example.org/trevor").unwrap();
+
+        assert_eq!("trevor", build_query(&url));
+    }
+
+    #[test]
+    fn test_build_query_empty() {
+        let url = Url::parse("finger://example.org").unwrap();
+
+        assert_eq!("", build_query(&url));
+    }
+}
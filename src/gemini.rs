@@ -15,6 +15,36 @@ impl Document {
     pub fn lines(&self) -> &Vec<Line> {
         &self.lines
     }
+
+    // the readable text of the page, link names and preformatted blocks included
+    pub fn text(&self) -> String {
+        let mut text = vec![];
+
+        for line in &self.lines {
+            match line {
+                Line::Link { url, link_name } => {
+                    text.push(link_name.clone().unwrap_or_else(|| url.to_string()))
+                }
+                Line::Preformatted { lines, .. } => text.extend(
+                    lines
+                        .iter()
+                        .filter_map(Line::get_content)
+                        .map(str::to_string),
+                ),
+                line => text.extend(line.get_content().map(str::to_string)),
+            }
+        }
+
+        text.join("\n")
+    }
+
+    // the first heading is the closest thing gemtext has to a title
+    pub fn title(&self) -> Option<&str> {
+        self.lines.iter().find_map(|line| match line {
+            Line::Heading { content, .. } => Some(content.as_str()),
+            _ => None,
+        })
+    }
 }
 
 pub fn build_document(input: &[u8], url: &Url) -> anyhow::Result<Document> {
@@ -29,6 +59,25 @@ pub fn build_document(input: &[u8], url: &Url) -> anyhow::Result<Document> {
         .map_err(|e| anyhow!("failed to parse u8 to utf8 in gemini document: {}", e))
 }
 
+pub fn build_link_list_document(input: &[u8], url: &Url) -> anyhow::Result<Document> {
+    info!("building link list document for: {}", url.to_string());
+
+    let mut input = String::from_utf8_lossy(input).trim().to_string();
+    input.push_str("\r\n");
+
+    parser::parse_link_list(&input, url)
+        .map(|(_, d)| d)
+        .map_err(|e| anyhow!("failed to parse link list document: {}", e))
+}
+
+pub fn build_preformatted_document(input: &[u8], alt_text: Option<&str>) -> Document {
+    let input = String::from_utf8_lossy(input);
+
+    let lines = input.lines().map(Line::text).collect();
+
+    Document::new(vec![Line::preformatted(alt_text, lines)])
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Line {
     Text {
@@ -133,6 +182,14 @@ mod parser {
         )(i)
     }
 
+    // only links are meaningful, everything else is plain text
+    pub fn parse_link_list<'a>(i: &'a str, base_url: &'a Url) -> IResult<&'a str, Document> {
+        map(
+            all_consuming(many0(terminated(alt((link(base_url), text)), line_ending))),
+            Document::new,
+        )(i)
+    }
+
     fn line<'a>(base_url: &'a Url) -> impl FnMut(&'a str) -> IResult<&'a str, Line> {
         alt((
             link(base_url),
@@ -206,7 +263,7 @@ mod parser {
     fn simple_line<'a>(
         prefix: &'a str,
         constructor: &'a dyn Fn(&str) -> Line,
-    ) -> impl FnMut(&'a str) -> IResult<&str, Line> {
+    ) -> impl FnMut(&'a str) -> IResult<&'a str, Line> {
         map(
             preceded(tag(prefix), map(not_line_ending, str::trim)),
             constructor,
@@ -260,6 +317,18 @@ mod parser {
             assert_eq!(expected, actual);
         }
 
+        #[test]
+        fn test_build_preformatted_document() {
+            let actual = build_preformatted_document(b"=> not a link\r\n# not a heading\n", None);
+
+            let expected = Document::new(vec![Line::preformatted(
+                None,
+                vec![Line::text("=> not a link"), Line::text("# not a heading")],
+            )]);
+
+            assert_eq!(expected, actual);
+        }
+
         #[test]
         fn test_parse_text_quote_link() {
             let actual = parse_with_example_url(
@@ -275,6 +344,24 @@ mod parser {
             assert_eq!(expected, actual);
         }
 
+        #[test]
+        fn test_parse_link_list() {
+            let actual = parse_link_list(
+                "# Not a heading\n=> docs/ Docs\n=> notes.txt\n",
+                &"nex://example.org/".parse().unwrap(),
+            )
+            .unwrap()
+            .1;
+
+            let expected = Document::new(vec![
+                Line::text("# Not a heading"),
+                Line::link("nex://example.org/docs/".parse().unwrap(), Some("Docs")),
+                Line::link("nex://example.org/notes.txt".parse().unwrap(), None),
+            ]);
+
+            assert_eq!(expected, actual);
+        }
+
         #[test]
         fn test_line_text() {
             assert_eq!(
@@ -0,0 +1,245 @@
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use log::{info, warn};
+use mime::Mime;
+
+use crate::db::Db;
+use crate::download::{default_open_command, spawn_command};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Ask,
+    Save,
+    Open,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Ask => write!(f, "ask"),
+            Action::Save => write!(f, "save"),
+            Action::Open => write!(f, "open"),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ask" => Ok(Action::Ask),
+            "save" => Ok(Action::Save),
+            "open" => Ok(Action::Open),
+            _ => anyhow::bail!("unknown handler action: {}", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Handler {
+    pattern: String,
+    command: String,
+}
+
+impl Handler {
+    pub fn new(pattern: &str, command: &str) -> Self {
+        Self {
+            pattern: pattern.trim().to_ascii_lowercase(),
+            command: command.trim().to_string(),
+        }
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    // patterns are a full type, a type/* wildcard or */*
+    pub fn matches(&self, mime: &Mime) -> bool {
+        match self.pattern.split_once('/') {
+            Some(("*", "*")) => true,
+            Some((type_, "*")) => type_ == mime.type_().as_str(),
+            Some(_) => self.pattern == mime.essence_str(),
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HandlerTable {
+    handlers: Vec<Handler>,
+}
+
+impl HandlerTable {
+    pub fn new(handlers: Vec<Handler>) -> Self {
+        Self { handlers }
+    }
+
+    // one entry per line, `type/subtype; command; flags`, flags are ignored
+    pub fn parse(mailcap: &str) -> Self {
+        let handlers = mailcap
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.split(';');
+                let pattern = fields.next()?;
+                let command = fields.next().filter(|c| !c.trim().is_empty())?;
+
+                Some(Handler::new(pattern, command))
+            })
+            .collect();
+
+        Self { handlers }
+    }
+
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(mailcap) => Self::parse(&mailcap),
+            Err(e) => {
+                warn!("failed to read mailcap {}, using defaults: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    // like mailcap, the first matching entry wins
+    pub fn find(&self, mime: &Mime) -> Option<&Handler> {
+        self.handlers.iter().find(|handler| handler.matches(mime))
+    }
+
+    pub fn handlers(&self) -> &[Handler] {
+        &self.handlers
+    }
+}
+
+impl Default for HandlerTable {
+    fn default() -> Self {
+        let command = format!("{} %s", default_open_command());
+
+        Self::new(
+            ["image/*", "audio/*", "video/*", "application/pdf"]
+                .iter()
+                .map(|pattern| Handler::new(pattern, &command))
+                .collect(),
+        )
+    }
+}
+
+pub struct Dispatcher {
+    handlers: HandlerTable,
+    db: Rc<Db>,
+}
+
+impl fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Dispatcher")
+    }
+}
+
+impl Dispatcher {
+    pub fn new(handlers: HandlerTable, db: Rc<Db>) -> Self {
+        Self { handlers, db }
+    }
+
+    pub fn command_for(&self, mime: &Mime) -> Option<&str> {
+        self.handlers.find(mime).map(Handler::command)
+    }
+
+    // without a handler there is nothing to open, so the download is simply kept
+    pub fn action_for(&self, mime: &Mime) -> Action {
+        if self.handlers.find(mime).is_none() {
+            return Action::Save;
+        }
+
+        match self.db.get_handler_action(mime.essence_str()) {
+            Ok(Some(action)) => action.parse().unwrap_or(Action::Ask),
+            Ok(None) => Action::Ask,
+            Err(e) => {
+                warn!("failed to read handler action: {}", e);
+                Action::Ask
+            }
+        }
+    }
+
+    pub fn remember(&self, mime: &Mime, action: Action) {
+        info!("remembering {} for {}", action, mime.essence_str());
+
+        if let Err(e) = self
+            .db
+            .set_handler_action(mime.essence_str(), &action.to_string())
+        {
+            warn!("failed to remember handler action: {}", e);
+        }
+    }
+
+    pub fn open(&self, mime: &Mime, path: &Path) -> anyhow::Result<()> {
+        let command = self
+            .command_for(mime)
+            .ok_or_else(|| anyhow::anyhow!("no handler for {}", mime.essence_str()))?;
+
+        spawn_command(command, path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAILCAP: &str = r#"
+        # viewers
+        image/png; feh %s
+        image/*; sxiv %s; needsterminal
+        audio/mpeg; mpv
+        broken line without command
+        video/*;
+    "#;
+
+    #[test]
+    fn test_parse_mailcap() {
+        let table = HandlerTable::parse(MAILCAP);
+
+        assert_eq!(
+            vec![
+                Handler::new("image/png", "feh %s"),
+                Handler::new("image/*", "sxiv %s"),
+                Handler::new("audio/mpeg", "mpv"),
+            ],
+            table.handlers()
+        );
+    }
+
+    #[test]
+    fn test_find_handler() {
+        let table = HandlerTable::parse(MAILCAP);
+        let find = |mime: &str| table.find(&mime.parse().unwrap()).map(Handler::command);
+
+        assert_eq!(Some("feh %s"), find("image/png"));
+        assert_eq!(Some("sxiv %s"), find("image/webp"));
+        assert_eq!(Some("mpv"), find("audio/mpeg"));
+        assert_eq!(None, find("audio/ogg"));
+        assert_eq!(None, find("application/pdf"));
+    }
+
+    #[test]
+    fn test_dispatcher_remembers_action() {
+        let db = Rc::new(Db::new(":memory:").unwrap());
+        db.prepare().unwrap();
+
+        let dispatcher = Dispatcher::new(HandlerTable::parse(MAILCAP), db);
+        let png: Mime = "image/png".parse().unwrap();
+
+        assert_eq!(Action::Ask, dispatcher.action_for(&png));
+        assert_eq!(Action::Save, dispatcher.action_for(&mime::APPLICATION_PDF));
+
+        dispatcher.remember(&png, Action::Open);
+        assert_eq!(Action::Open, dispatcher.action_for(&png));
+    }
+}
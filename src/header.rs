@@ -1,6 +1,6 @@
 use core::fmt;
 
-use anyhow::anyhow;
+use log::warn;
 use mime::Mime;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -95,6 +95,34 @@ impl From<u8> for Status {
     }
 }
 
+impl Status {
+    pub fn code(&self) -> u8 {
+        use Status::*;
+
+        match self {
+            Input => 10,
+            InputSensitive => 11,
+            Success => 20,
+            RedirectTemporary => 30,
+            RedirectPermanent => 31,
+            TemporaryFailure => 40,
+            ServerUnavailable => 41,
+            CgiError => 42,
+            ProxyError => 43,
+            SlowDown => 44,
+            PermanentFailure => 50,
+            NotFound => 51,
+            Gone => 52,
+            ProxyRequestRefused => 53,
+            BadRequest => 59,
+            ClientCertificateRequired => 60,
+            CertificateNotAuthorized => 61,
+            CertificateNotValid => 62,
+            Unknown(code) => *code,
+        }
+    }
+}
+
 impl fmt::Display for Status {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:#?}", self)
@@ -105,6 +133,7 @@ impl fmt::Display for Status {
 pub struct Header {
     status: Status,
     inner: Inner,
+    deviations: Vec<Deviation>,
 }
 
 impl fmt::Display for Header {
@@ -123,12 +152,22 @@ impl Header {
         &self.inner
     }
 
+    pub fn deviations(&self) -> &[Deviation] {
+        &self.deviations
+    }
+
+    fn with_deviations(mut self, deviations: Vec<Deviation>) -> Self {
+        self.deviations = deviations;
+        self
+    }
+
     pub fn input(status: Status, prompt: &str) -> Self {
         Self {
             status,
             inner: Inner::Input {
                 prompt: Self::prepare_str(prompt),
             },
+            deviations: vec![],
         }
     }
 
@@ -136,6 +175,7 @@ impl Header {
         Self {
             status,
             inner: Inner::Success { mime },
+            deviations: vec![],
         }
     }
 
@@ -143,6 +183,7 @@ impl Header {
         Self {
             status,
             inner: Inner::Redirect { url },
+            deviations: vec![],
         }
     }
 
@@ -152,6 +193,7 @@ impl Header {
             inner: Inner::Failure {
                 error: Self::prepare_str(error),
             },
+            deviations: vec![],
         }
     }
 
@@ -161,6 +203,32 @@ impl Header {
             inner: Inner::ClientCertificateRequired {
                 error: Self::prepare_str(error),
             },
+            deviations: vec![],
+        }
+    }
+
+    // 44 carries the number of seconds to wait in its meta
+    pub fn slow_down_seconds(&self) -> Option<u64> {
+        match (&self.status, &self.inner) {
+            (Status::SlowDown, Inner::Failure { error: Some(meta) }) => meta.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    // the header as it was sent, without the trailing CRLF
+    pub fn to_line(&self) -> String {
+        let meta = match &self.inner {
+            Inner::Input { prompt: meta }
+            | Inner::Failure { error: meta }
+            | Inner::ClientCertificateRequired { error: meta } => meta.clone().unwrap_or_default(),
+            Inner::Success { mime } => mime.to_string(),
+            Inner::Redirect { url } => url.to_string(),
+        };
+
+        if meta.is_empty() {
+            format!("{:02}", self.status.code())
+        } else {
+            format!("{:02} {}", self.status.code(), meta)
         }
     }
 
@@ -188,10 +256,292 @@ impl fmt::Display for Inner {
     }
 }
 
-pub fn build_header(input: &[u8]) -> anyhow::Result<(Header, Option<Vec<u8>>)> {
-    parser::parse(std::str::from_utf8(input)?)
-        .map(|(body, header)| (header, Some(body.as_bytes().to_vec())))
-        .map_err(|_| anyhow!("failed to parse bytes to utf8 in header"))
+// things real servers get wrong that lenient parsing tolerates
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Deviation {
+    BareLineFeed,
+    MissingSpace,
+    EmptySuccessMeta,
+    SingleDigitStatus,
+}
+
+impl fmt::Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BareLineFeed => write!(f, "header ends with LF instead of CRLF"),
+            Self::MissingSpace => write!(f, "missing space between status and meta"),
+            Self::EmptySuccessMeta => write!(f, "empty mime type on a success response"),
+            Self::SingleDigitStatus => write!(f, "status code has a single digit"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ParseMode {
+    Strict,
+    Lenient,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderError {
+    BadStatus(String),
+    InvalidMeta(String),
+    MetaTooLong(usize),
+    MissingCrlf,
+    BadMime(String),
+    NonConforming(Vec<Deviation>),
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadStatus(status) => write!(f, "bad status code: {:?}", status),
+            Self::InvalidMeta(meta) => write!(f, "invalid meta: {:?}", meta),
+            Self::MetaTooLong(length) => {
+                write!(f, "meta is {} bytes, the limit is 1024 bytes", length)
+            }
+            Self::MissingCrlf => write!(f, "header is missing its CRLF line ending"),
+            Self::BadMime(mime) => write!(f, "bad mime type: {:?}", mime),
+            Self::NonConforming(deviations) => write!(
+                f,
+                "header does not conform to the specification: {}",
+                deviations
+                    .iter()
+                    .map(Deviation::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HeaderError {}
+
+pub fn build_header(
+    input: &[u8],
+    mode: ParseMode,
+) -> Result<(Header, Option<Vec<u8>>), HeaderError> {
+    // only the header line is text, the body can be anything the mime type says it is
+    let (line, body) = split_header_line(input).ok_or(HeaderError::MissingCrlf)?;
+
+    let line = std::str::from_utf8(line)
+        .map_err(|_| HeaderError::InvalidMeta(String::from_utf8_lossy(line).to_string()))?;
+
+    let header = parser::parse(line)?;
+
+    for deviation in header.deviations() {
+        warn!("tolerating header deviation: {}", deviation);
+    }
+
+    if mode == ParseMode::Strict && !header.deviations().is_empty() {
+        return Err(HeaderError::NonConforming(header.deviations().to_vec()));
+    }
+
+    Ok((header, Some(body.to_vec())))
+}
+
+fn split_header_line(input: &[u8]) -> Option<(&[u8], &[u8])> {
+    let end = input.iter().position(|&b| b == b'\n')?;
+
+    Some(input.split_at(end + 1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PNG_SIGNATURE: &[u8] = &[
+        0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', 0xff, 0x00,
+    ];
+
+    #[test]
+    fn test_slow_down_seconds() {
+        assert_eq!(
+            Some(30),
+            Header::failure(Status::SlowDown, "30").slow_down_seconds()
+        );
+        assert_eq!(
+            None,
+            Header::failure(Status::SlowDown, "soon").slow_down_seconds()
+        );
+        assert_eq!(
+            None,
+            Header::failure(Status::TemporaryFailure, "30").slow_down_seconds()
+        );
+    }
+
+    #[test]
+    fn test_to_line() {
+        for line in [
+            "20 text/gemini; lang=en",
+            "44 30",
+            "51",
+            "31 gemini://example.org/",
+        ] {
+            let (header, _) =
+                build_header(format!("{}\r\n", line).as_bytes(), ParseMode::Strict).unwrap();
+
+            assert_eq!(line, header.to_line());
+        }
+    }
+
+    #[test]
+    fn test_build_header_binary_body() {
+        let input = [b"20 image/png\r\n".as_slice(), PNG_SIGNATURE].concat();
+
+        let (header, body) = build_header(&input, ParseMode::Lenient).unwrap();
+
+        assert_eq!(header.status(), Status::Success);
+        assert_eq!(body.unwrap(), PNG_SIGNATURE);
+    }
+
+    #[test]
+    fn test_build_header_invalid_utf8_body() {
+        let input = [
+            b"20 text/plain; charset=iso-8859-1\r\n".as_slice(),
+            &[0xe9, 0xe8],
+        ]
+        .concat();
+
+        let (header, body) = build_header(&input, ParseMode::Lenient).unwrap();
+
+        match header.inner() {
+            Inner::Success { mime } => assert_eq!(mime.essence_str(), "text/plain"),
+            _ => unreachable!(),
+        }
+        assert_eq!(body.unwrap(), vec![0xe9, 0xe8]);
+    }
+
+    #[test]
+    fn test_build_header_empty_body() {
+        let (_, body) = build_header(b"20 text/gemini\r\n", ParseMode::Strict).unwrap();
+
+        assert!(body.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_build_header_missing_line_ending() {
+        assert_eq!(
+            build_header(b"20 text/gemini", ParseMode::Lenient).unwrap_err(),
+            HeaderError::MissingCrlf
+        );
+    }
+
+    #[test]
+    fn test_build_header_bad_status() {
+        assert_eq!(
+            build_header(b"2x text/gemini\r\n", ParseMode::Lenient).unwrap_err(),
+            HeaderError::BadStatus("2x".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_header_unknown_status() {
+        assert_eq!(
+            build_header(b"99 nope\r\n", ParseMode::Lenient).unwrap_err(),
+            HeaderError::BadStatus("99".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_header_bad_mime() {
+        assert_eq!(
+            build_header(b"20 not a mime\r\n", ParseMode::Lenient).unwrap_err(),
+            HeaderError::BadMime("not a mime".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_header_bad_redirect() {
+        assert_eq!(
+            build_header(b"30 ::nowhere\r\n", ParseMode::Lenient).unwrap_err(),
+            HeaderError::InvalidMeta("::nowhere".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_header_meta_too_long() {
+        let input = format!("51 {}\r\n", "x".repeat(1025));
+
+        assert_eq!(
+            build_header(input.as_bytes(), ParseMode::Lenient).unwrap_err(),
+            HeaderError::MetaTooLong(1025)
+        );
+    }
+
+    fn deviations(input: &[u8]) -> Vec<Deviation> {
+        build_header(input, ParseMode::Lenient)
+            .unwrap()
+            .0
+            .deviations()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_lenient_bare_line_feed() {
+        assert_eq!(
+            deviations(b"20 text/gemini\n"),
+            vec![Deviation::BareLineFeed]
+        );
+    }
+
+    #[test]
+    fn test_lenient_missing_space() {
+        assert_eq!(
+            deviations(b"20text/gemini\r\n"),
+            vec![Deviation::MissingSpace]
+        );
+    }
+
+    #[test]
+    fn test_lenient_empty_success_meta() {
+        let (header, _) = build_header(b"20 \r\n", ParseMode::Lenient).unwrap();
+
+        match header.inner() {
+            Inner::Success { mime } => {
+                assert_eq!(mime.essence_str(), "text/gemini");
+                assert_eq!(mime.get_param(mime::CHARSET).unwrap(), "utf-8");
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(header.deviations(), &[Deviation::EmptySuccessMeta]);
+    }
+
+    #[test]
+    fn test_lenient_single_digit_status() {
+        let (header, _) = build_header(b"3 gemini://example.org/\n", ParseMode::Lenient).unwrap();
+
+        assert_eq!(header.status(), Status::RedirectTemporary);
+        assert_eq!(
+            header.deviations(),
+            &[Deviation::BareLineFeed, Deviation::SingleDigitStatus]
+        );
+    }
+
+    #[test]
+    fn test_strict_reports_deviations() {
+        assert_eq!(
+            build_header(b"2\n", ParseMode::Strict).unwrap_err(),
+            HeaderError::NonConforming(vec![
+                Deviation::BareLineFeed,
+                Deviation::SingleDigitStatus,
+                Deviation::EmptySuccessMeta,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_strict_accepts_conforming_header() {
+        assert!(build_header(b"51 Not found\r\n", ParseMode::Strict).is_ok());
+    }
+
+    #[test]
+    fn test_build_header_invalid_utf8_meta() {
+        assert!(matches!(
+            build_header(b"51 \xff\r\n", ParseMode::Lenient).unwrap_err(),
+            HeaderError::InvalidMeta(_)
+        ));
+    }
 }
 
 mod parser {
@@ -199,57 +549,106 @@ mod parser {
 
     use Status::*;
 
-    use nom::bytes::complete::{tag, take};
-    use nom::character::complete::{line_ending, not_line_ending};
-    use nom::combinator::map_res;
-    use nom::sequence::{separated_pair, terminated};
+    use nom::branch::alt;
+    use nom::bytes::complete::{tag, take_while_m_n};
+    use nom::combinator::{eof, map_res, peek};
+    use nom::sequence::terminated;
     use nom::IResult;
 
+    const CRLF: &str = "\r\n";
+    const LF: &str = "\n";
     const SPACE: &str = " ";
+    const DEFAULT_MIME: &str = "text/gemini; charset=utf-8";
+    const MAX_META_LENGTH: usize = 1024;
 
     #[rustfmt::skip]
-    pub fn parse(i: &str) -> IResult<&str, Header> {
+    pub fn parse(i: &str) -> Result<Header, HeaderError> {
 
-        let (rest, (status, meta)) = parse_gemini_header(i)?;
+        let mut deviations = vec![];
 
-        Ok((
-            rest,
-            match status {
-                Input | InputSensitive => {
-                    Header::input(status, meta)
-                }
-                Success => {
-                    Header::success(status, meta.parse().unwrap())
-                }
-                RedirectTemporary | RedirectPermanent => {
-                    Header::redirect(status, meta.parse().unwrap())
-                }
-                TemporaryFailure | ServerUnavailable | CgiError | ProxyError | SlowDown | PermanentFailure | NotFound | Gone | ProxyRequestRefused | BadRequest => {
-                    Header::failure(status, meta)
-                }
-                ClientCertificateRequired | CertificateNotAuthorized | CertificateNotValid => {
-                    Header::client_certificate(status, meta)
-                }
-                Unknown(status) => {
-                    panic!("Unknown status code: {} | meta: {}", status, meta)
-                }
-            },
-        ))
+        let line = match (i.strip_suffix(CRLF), i.strip_suffix(LF)) {
+            (Some(line), _) => line,
+            (None, Some(line)) => {
+                deviations.push(Deviation::BareLineFeed);
+                line
+            }
+            (None, None) => return Err(HeaderError::MissingCrlf),
+        };
+
+        let (rest, status) = match status_code_digits(line) {
+            Ok((rest, status)) => (rest, status),
+            Err(_) => {
+                let (rest, status) = status_code_digit(line)
+                    .map_err(|_| HeaderError::BadStatus(line.chars().take(2).collect()))?;
+
+                deviations.push(Deviation::SingleDigitStatus);
+                (rest, status * 10)
+            }
+        };
+
+        let status = Status::from(status);
+
+        let meta = match rest.strip_prefix(SPACE) {
+            Some(meta) => meta,
+            None if rest.is_empty() => rest,
+            None => {
+                deviations.push(Deviation::MissingSpace);
+                rest
+            }
+        };
+
+        if meta.contains(['\r', '\n']) {
+            return Err(HeaderError::InvalidMeta(meta.to_string()));
+        }
+
+        if meta.len() > MAX_META_LENGTH {
+            return Err(HeaderError::MetaTooLong(meta.len()));
+        }
+
+        let header = match status {
+            Input | InputSensitive => {
+                Header::input(status, meta)
+            }
+            Success if meta.trim().is_empty() => {
+                deviations.push(Deviation::EmptySuccessMeta);
+                Header::success(status, DEFAULT_MIME.parse().unwrap())
+            }
+            Success => {
+                Header::success(status, meta.parse().map_err(|_| HeaderError::BadMime(meta.to_string()))?)
+            }
+            RedirectTemporary | RedirectPermanent => {
+                Header::redirect(status, meta.parse().map_err(|_| HeaderError::InvalidMeta(meta.to_string()))?)
+            }
+            TemporaryFailure | ServerUnavailable | CgiError | ProxyError | SlowDown | PermanentFailure | NotFound | Gone | ProxyRequestRefused | BadRequest => {
+                Header::failure(status, meta)
+            }
+            ClientCertificateRequired | CertificateNotAuthorized | CertificateNotValid => {
+                Header::client_certificate(status, meta)
+            }
+            Unknown(status) => {
+                return Err(HeaderError::BadStatus(format!("{:02}", status)))
+            }
+        };
+
+        Ok(header.with_deviations(deviations))
     }
 
-    fn parse_gemini_header(i: &str) -> IResult<&str, (Status, &str)> {
+    // a lone digit followed by the separator (or nothing), e.g. "2 text/gemini"
+    fn status_code_digit(i: &str) -> IResult<&str, u8> {
         terminated(
-            separated_pair(
-                map_res(status_code_digits, Status::try_from),
-                tag(SPACE),
-                not_line_ending,
+            map_res(
+                take_while_m_n(1, 1, |c: char| c.is_ascii_digit()),
+                str::parse,
             ),
-            line_ending,
+            peek(alt((tag(SPACE), eof))),
         )(i)
     }
 
     fn status_code_digits(i: &str) -> IResult<&str, u8> {
-        map_res(take(2usize), str::parse)(i)
+        map_res(
+            take_while_m_n(2, 2, |c: char| c.is_ascii_digit()),
+            str::parse,
+        )(i)
     }
 
     #[cfg(test)]
@@ -266,11 +665,13 @@ mod parser {
 
         #[test]
         fn test_parse_success() {
-            match parse("20 text/gemini\r\n").unwrap().1 {
+            match parse("20 text/gemini\r\n").unwrap() {
                 Header {
                     status,
                     inner: Inner::Success { mime },
+                    deviations,
                 } => {
+                    assert!(deviations.is_empty());
                     assert_eq!(status, Status::Success);
                     assert_eq!(mime.essence_str(), "text/gemini");
                 }
@@ -280,11 +681,13 @@ mod parser {
 
         #[test]
         fn test_parse_success_range() {
-            match parse("25 text/gemini\r\n").unwrap().1 {
+            match parse("25 text/gemini\r\n").unwrap() {
                 Header {
                     status,
                     inner: Inner::Success { mime },
+                    deviations,
                 } => {
+                    assert!(deviations.is_empty());
                     assert_eq!(status, Status::Success);
                     assert_eq!(mime.essence_str(), "text/gemini");
                 }
@@ -294,11 +697,13 @@ mod parser {
 
         #[test]
         fn test_parse_input() {
-            match parse("10 What is your name?\r\n").unwrap().1 {
+            match parse("10 What is your name?\r\n").unwrap() {
                 Header {
                     status,
                     inner: Inner::Input { prompt },
+                    deviations,
                 } => {
+                    assert!(deviations.is_empty());
                     assert_eq!(status, Status::Input);
                     assert_eq!(prompt, Some("What is your name?".to_string()));
                 }
@@ -308,11 +713,13 @@ mod parser {
 
         #[test]
         fn test_parse_input_sensitive() {
-            match parse("11 Would you like to play a game?\r\n").unwrap().1 {
+            match parse("11 Would you like to play a game?\r\n").unwrap() {
                 Header {
                     status,
                     inner: Inner::Input { prompt },
+                    deviations,
                 } => {
+                    assert!(deviations.is_empty());
                     assert_eq!(status, Status::InputSensitive);
                     assert_eq!(prompt, Some("Would you like to play a game?".to_string()));
                 }
@@ -0,0 +1,484 @@
+use log::info;
+use url::Url;
+
+use crate::gemini::{Document, Line};
+
+// contents of these are never shown
+const SKIPPED: [&str; 6] = ["script", "style", "head", "title", "template", "noscript"];
+
+const BLOCKS: [&str; 16] = [
+    "p", "div", "section", "article", "header", "footer", "nav", "main", "aside", "table", "tr",
+    "dl", "dt", "dd", "figure", "form",
+];
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Text(String),
+    Start {
+        name: String,
+        attributes: Vec<(String, String)>,
+    },
+    End(String),
+}
+
+// not a web engine: headings, paragraphs, lists, quotes, pre blocks and links survive
+pub fn build_html_document(input: &[u8], url: &Url) -> Document {
+    info!("building html document for: {}", url.to_string());
+
+    let input = String::from_utf8_lossy(input);
+    let mut converter = Converter::new(url);
+
+    for token in tokenize(&input) {
+        converter.token(token);
+    }
+
+    converter.finish()
+}
+
+struct Converter<'a> {
+    base_url: &'a Url,
+    lines: Vec<Line>,
+    text: String,
+    links: Vec<Line>,
+    link: Option<(String, String)>,
+    heading: Option<usize>,
+    list_depth: usize,
+    quote_depth: usize,
+    pre: Option<String>,
+}
+
+impl<'a> Converter<'a> {
+    fn new(base_url: &'a Url) -> Self {
+        Self {
+            base_url,
+            lines: vec![],
+            text: String::new(),
+            links: vec![],
+            link: None,
+            heading: None,
+            list_depth: 0,
+            quote_depth: 0,
+            pre: None,
+        }
+    }
+
+    fn token(&mut self, token: Token) {
+        match token {
+            Token::Text(text) => self.push_text(&text),
+            Token::Start { name, attributes } => self.start(&name, &attributes),
+            Token::End(name) => self.end(&name),
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if let Some(pre) = &mut self.pre {
+            pre.push_str(text);
+            return;
+        }
+
+        // collapse whitespace the way a browser would
+        let words = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if text.starts_with(char::is_whitespace)
+            && !self.text.is_empty()
+            && !self.text.ends_with(' ')
+        {
+            self.text.push(' ');
+        }
+
+        self.text.push_str(&words);
+
+        if text.ends_with(char::is_whitespace) && !words.is_empty() {
+            self.text.push(' ');
+        }
+
+        if let Some((_, link_text)) = &mut self.link {
+            link_text.push_str(text);
+        }
+    }
+
+    fn start(&mut self, name: &str, attributes: &[(String, String)]) {
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str())
+        };
+
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                self.heading = name[1..].parse().ok();
+            }
+            "ul" | "ol" => {
+                self.flush();
+                self.list_depth += 1;
+            }
+            "li" => self.flush(),
+            "blockquote" => {
+                self.flush();
+                self.quote_depth += 1;
+            }
+            "pre" => {
+                self.flush();
+                self.pre = Some(String::new());
+            }
+            "br" | "hr" => self.flush(),
+            "a" => {
+                if let Some(href) = attribute("href") {
+                    self.link = Some((href.to_string(), String::new()));
+                }
+            }
+            "img" => {
+                if let Some(src) = attribute("src") {
+                    let alt = attribute("alt").unwrap_or_default();
+
+                    self.push_text(alt);
+                    self.push_link(src, alt);
+                }
+            }
+            name if BLOCKS.contains(&name) => self.flush(),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                self.heading = None;
+                self.blank();
+            }
+            "ul" | "ol" => {
+                self.flush();
+                self.list_depth = self.list_depth.saturating_sub(1);
+
+                if self.list_depth == 0 {
+                    self.blank();
+                }
+            }
+            "li" => self.flush(),
+            "blockquote" => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                self.blank();
+            }
+            "pre" => {
+                if let Some(pre) = self.pre.take() {
+                    let pre = pre.trim_matches('\n');
+
+                    self.lines.push(Line::preformatted(
+                        None,
+                        pre.lines().map(Line::text).collect(),
+                    ));
+                    self.blank();
+                }
+            }
+            "a" => {
+                if let Some((href, text)) = self.link.take() {
+                    let name = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+                    self.push_link(&href, &name);
+                }
+            }
+            "p" => {
+                self.flush();
+                self.blank();
+            }
+            name if BLOCKS.contains(&name) => self.flush(),
+            _ => {}
+        }
+    }
+
+    fn push_link(&mut self, href: &str, name: &str) {
+        // javascript: and friends cannot be followed
+        match self.base_url.join(href) {
+            Ok(url) if !matches!(url.scheme(), "javascript" | "data") => {
+                let name = Some(name.trim()).filter(|name| !name.is_empty());
+                self.links.push(Line::link(url, name));
+            }
+            _ => {}
+        }
+    }
+
+    // turns the collected inline text into a line for the current block
+    fn flush(&mut self) {
+        let text = self.text.trim().to_string();
+        self.text.clear();
+
+        if !text.is_empty() {
+            let line = if let Some(level) = self.heading {
+                Line::heading(&text, level.clamp(1, 3))
+            } else if self.list_depth > 0 {
+                Line::unordered_list_item(&text)
+            } else if self.quote_depth > 0 {
+                Line::quote(&text)
+            } else {
+                Line::text(&text)
+            };
+
+            self.lines.push(line);
+        }
+
+        self.lines.append(&mut self.links);
+    }
+
+    fn blank(&mut self) {
+        let ends_blank =
+            matches!(self.lines.last(), Some(Line::Text { content }) if content.is_empty());
+
+        if !self.lines.is_empty() && !ends_blank {
+            self.lines.push(Line::text(""));
+        }
+    }
+
+    fn finish(mut self) -> Document {
+        if let Some(pre) = self.pre.take() {
+            self.push_text(&pre);
+        }
+
+        self.flush();
+
+        if matches!(self.lines.last(), Some(Line::Text { content }) if content.is_empty()) {
+            self.lines.pop();
+        }
+
+        Document::new(self.lines)
+    }
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        let start = match rest.find('<') {
+            Some(start) => start,
+            None => {
+                tokens.push(Token::Text(decode_entities(rest)));
+                break;
+            }
+        };
+
+        if start > 0 {
+            tokens.push(Token::Text(decode_entities(&rest[..start])));
+        }
+
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => {
+                tokens.push(Token::Text(decode_entities(rest)));
+                break;
+            }
+        };
+
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(Token::End(name.trim().to_ascii_lowercase()));
+            continue;
+        }
+
+        // doctypes and processing instructions
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+
+        let (name, attributes) = parse_tag(tag);
+
+        if SKIPPED.contains(&name.as_str()) && !tag.ends_with('/') {
+            let close = format!("</{}", name);
+
+            rest = rest
+                .to_ascii_lowercase()
+                .find(&close)
+                .and_then(|index| rest[index..].find('>').map(|end| &rest[index + end + 1..]))
+                .unwrap_or("");
+            continue;
+        }
+
+        tokens.push(Token::Start { name, attributes });
+    }
+
+    tokens
+}
+
+fn parse_tag(tag: &str) -> (String, Vec<(String, String)>) {
+    let tag = tag.trim_end_matches('/').trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = tag[..name_end].to_ascii_lowercase();
+
+    let mut attributes = vec![];
+    let mut rest = tag[name_end..].trim_start();
+
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_ascii_lowercase();
+        rest = rest[key_end..].trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            Some(value) => {
+                let value = value.trim_start();
+
+                let (value, remaining) = match value.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let value = &value[1..];
+                        let end = value.find(quote).unwrap_or(value.len());
+                        (&value[..end], value.get(end + 1..).unwrap_or(""))
+                    }
+                    _ => {
+                        let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                        (&value[..end], &value[end..])
+                    }
+                };
+
+                rest = remaining.trim_start();
+                decode_entities(value)
+            }
+            None => String::new(),
+        };
+
+        if !key.is_empty() {
+            attributes.push((key, value));
+        }
+    }
+
+    (name, attributes)
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+
+            c.map(|c| (c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                output.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build(input: &str) -> Vec<Line> {
+        let url = Url::parse("gemini://example.org/proxy/page.html").unwrap();
+
+        build_html_document(input.as_bytes(), &url).lines().clone()
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert_eq!(
+            vec![
+                Line::heading("Title", 1),
+                Line::text(""),
+                Line::text("Some emphasised text."),
+                Line::text(""),
+                Line::unordered_list_item("one"),
+                Line::unordered_list_item("two"),
+                Line::text(""),
+                Line::quote("quoted"),
+            ],
+            build(
+                "<!DOCTYPE html><html><head><title>Ignored</title></head><body>\
+                 <h1>Title</h1><p>Some <em>emphasised</em>\n   text.</p>\
+                 <ul><li>one</li><li>two</li></ul><blockquote>quoted</blockquote></body></html>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        assert_eq!(
+            vec![
+                Line::text("Read the docs now."),
+                Line::link(
+                    "gemini://example.org/docs/".parse().unwrap(),
+                    Some("the docs")
+                ),
+                Line::text(""),
+                Line::text("js"),
+                Line::text(""),
+                Line::text("A cat"),
+                Line::link(
+                    "https://example.com/cat.png".parse().unwrap(),
+                    Some("A cat")
+                ),
+            ],
+            build(
+                "<p>Read <a href=\"/docs/\">the docs</a> now.</p>\
+                 <p><a href='javascript:void(0)'>js</a></p>\
+                 <p><img src=https://example.com/cat.png alt=\"A cat\"></p>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_strips_scripts_and_styles() {
+        assert_eq!(
+            vec![Line::text("Visible")],
+            build("<style>p { color: red }</style><script>alert('<p>hi</p>')</script>Visible")
+        );
+    }
+
+    #[test]
+    fn test_pre_and_entities() {
+        assert_eq!(
+            vec![
+                Line::preformatted(
+                    None,
+                    vec![Line::text("fn main() {"), Line::text("    x < y")]
+                ),
+                Line::text(""),
+                Line::text("Fish & chips \u{e9}"),
+            ],
+            build("<pre>\nfn main() {\n    x &lt; y</pre>Fish &amp; chips &#233;")
+        );
+    }
+}
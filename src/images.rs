@@ -0,0 +1,95 @@
+use image::imageops::FilterType;
+use mime::Mime;
+use url::Url;
+
+use crate::mime_types;
+
+const SUPPORTED: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+pub fn is_supported(mime: &Mime) -> bool {
+    SUPPORTED.contains(&mime.essence_str())
+}
+
+// links carry no mime type, so the extension is all there is to go on
+pub fn is_image_url(url: &Url) -> bool {
+    is_supported(&mime_types::from_path(url.path()))
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedImage {
+    size: [usize; 2],
+    rgba: Vec<u8>,
+}
+
+impl DecodedImage {
+    pub fn size(&self) -> [usize; 2] {
+        self.size
+    }
+
+    pub fn rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+}
+
+// animated gifs only show their first frame
+pub fn decode(bytes: &[u8], max_dimension: u32) -> anyhow::Result<DecodedImage> {
+    let mut image = image::load_from_memory(bytes)?;
+
+    if image.width() > max_dimension || image.height() > max_dimension {
+        image = image.resize(max_dimension, max_dimension, FilterType::Triangle);
+    }
+
+    let image = image.to_rgba8();
+
+    Ok(DecodedImage {
+        size: [image.width() as usize, image.height() as usize],
+        rgba: image.into_raw(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use image::{ImageOutputFormat, RgbaImage};
+
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        image::DynamicImage::ImageRgba8(RgbaImage::new(width, height))
+            .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+            .unwrap();
+
+        bytes
+    }
+
+    #[test]
+    fn test_decode() {
+        let image = decode(&png(4, 2), 1024).unwrap();
+
+        assert_eq!([4, 2], image.size());
+        assert_eq!(4 * 2 * 4, image.rgba().len());
+    }
+
+    #[test]
+    fn test_decode_caps_size() {
+        assert_eq!([64, 16], decode(&png(256, 64), 64).unwrap().size());
+    }
+
+    #[test]
+    fn test_decode_garbage() {
+        assert!(decode(b"not an image", 1024).is_err());
+    }
+
+    #[test]
+    fn test_is_image_url() {
+        assert!(is_image_url(
+            &"gemini://example.org/cat.JPG".parse().unwrap()
+        ));
+        assert!(!is_image_url(
+            &"gemini://example.org/cat.gmi".parse().unwrap()
+        ));
+    }
+}
@@ -0,0 +1,66 @@
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Script {
+    Arabic,
+    Hebrew,
+    Cyrillic,
+    Greek,
+    Devanagari,
+    Thai,
+    Cjk,
+    Latin,
+}
+
+// the lang parameter can hold a list like "en,fr", only the first tag matters for layout
+pub fn primary_subtag(lang: &str) -> Option<String> {
+    lang.split(',')
+        .next()
+        .and_then(|tag| tag.trim().split(['-', '_']).next())
+        .filter(|subtag| !subtag.is_empty())
+        .map(str::to_ascii_lowercase)
+}
+
+pub fn script(lang: &str) -> Script {
+    match primary_subtag(lang).as_deref() {
+        Some("ar" | "fa" | "ur" | "ps" | "sd" | "ug" | "ckb") => Script::Arabic,
+        Some("he" | "iw" | "yi") => Script::Hebrew,
+        Some("ru" | "uk" | "be" | "bg" | "sr" | "mk" | "kk" | "ky" | "mn") => Script::Cyrillic,
+        Some("el") => Script::Greek,
+        Some("hi" | "mr" | "ne" | "sa") => Script::Devanagari,
+        Some("th") => Script::Thai,
+        Some("zh" | "ja" | "ko") => Script::Cjk,
+        _ => Script::Latin,
+    }
+}
+
+pub fn is_right_to_left(lang: &str) -> bool {
+    matches!(script(lang), Script::Arabic | Script::Hebrew)
+        || primary_subtag(lang).as_deref() == Some("dv")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_primary_subtag() {
+        assert_eq!(Some("ar".to_string()), primary_subtag("ar-EG"));
+        assert_eq!(Some("en".to_string()), primary_subtag("EN,fr"));
+        assert_eq!(None, primary_subtag(""));
+    }
+
+    #[test]
+    fn test_is_right_to_left() {
+        assert!(is_right_to_left("ar"));
+        assert!(is_right_to_left("he-IL"));
+        assert!(is_right_to_left("fa"));
+        assert!(!is_right_to_left("en"));
+        assert!(!is_right_to_left("ja"));
+    }
+
+    #[test]
+    fn test_script() {
+        assert_eq!(Script::Cjk, script("zh-Hant"));
+        assert_eq!(Script::Cyrillic, script("ru"));
+        assert_eq!(Script::Latin, script("de"));
+    }
+}
@@ -1,21 +1,47 @@
+mod about;
+mod bookmarks;
+mod cache;
+mod charset;
 mod client;
 mod db;
+mod download;
 mod event;
+mod file;
+mod finger;
 mod gemini;
+mod handlers;
 mod header;
+mod html;
+mod images;
+mod language;
+mod markdown;
+mod mime_types;
+mod net;
+mod nex;
 mod response;
+mod retry;
+mod search;
 mod settings;
+mod titan;
 mod tls;
+mod transfer;
 mod ui;
+mod watcher;
 
 use std::rc::Rc;
 
 use log::info;
 
-use client::GeminiClient;
+use about::AboutClient;
+use client::{Clients, GeminiClient};
 use db::Db;
 use event::EventBus;
+use file::FileClient;
+use finger::FingerClient;
+use nex::NexClient;
 use settings::Settings;
+use titan::TitanClient;
+use tls::load_identity;
 use tls::verification::TofuVerifier;
 use ui::DioscuriApp;
 
@@ -26,13 +52,38 @@ fn main() -> anyhow::Result<()> {
 
     let settings = Settings::new();
 
-    let db = Db::new(&settings.database_path())?;
+    let db = Rc::new(Db::new(&settings.database_path())?);
     db.prepare()?;
 
-    let tofu_verifier = Rc::new(TofuVerifier::new(db));
-    let gemini_client = GeminiClient::new(tofu_verifier)?;
+    let tofu_verifier = Rc::new(TofuVerifier::new(db.clone()));
+    let gemini_client = GeminiClient::new(
+        tofu_verifier.clone(),
+        settings.header_mode(),
+        settings.body_policy(),
+    )?;
+
+    let identity = match settings.client_identity_paths() {
+        Some((certificate_path, key_path)) => Some(load_identity(&certificate_path, &key_path)?),
+        None => None,
+    };
+
+    let titan_client = TitanClient::new(
+        tofu_verifier,
+        identity,
+        settings.header_mode(),
+        settings.body_policy(),
+    )?;
+
+    let clients = Clients::new(
+        gemini_client,
+        titan_client,
+        FingerClient::new(settings.body_policy()),
+        NexClient::new(settings.body_policy()),
+        FileClient::new(settings.body_policy()),
+        AboutClient::new(db.clone(), settings.clone()),
+    );
 
     let event_bus = EventBus::new();
-    let app = Box::new(DioscuriApp::new(settings, event_bus, gemini_client));
+    let app = Box::new(DioscuriApp::new(settings, event_bus, clients, db));
     eframe::run_native(app, Default::default());
 }
@@ -0,0 +1,324 @@
+use log::info;
+use url::Url;
+
+use crate::gemini::{Document, Line};
+
+const FENCES: [&str; 2] = ["```", "~~~"];
+
+// gemtext has no inline links, so links are lifted out of the text and follow the line they were in
+pub fn build_markdown_document(input: &[u8], url: &Url) -> Document {
+    info!("building markdown document for: {}", url.to_string());
+
+    let input = String::from_utf8_lossy(input);
+    let mut builder = Builder::new(url);
+    let mut lines = input.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        if let Some(fence) = FENCES.iter().find(|fence| trimmed.starts_with(*fence)) {
+            builder.flush();
+
+            let alt_text = trimmed.trim_start_matches(*fence).trim();
+            let code = lines
+                .by_ref()
+                .take_while(|line| !line.trim().starts_with(*fence))
+                .map(Line::text)
+                .collect();
+
+            builder.push(Line::preformatted(
+                Some(alt_text).filter(|alt| !alt.is_empty()),
+                code,
+            ));
+
+            continue;
+        }
+
+        // setext headings underline the previous paragraph line
+        if let Some(next) = lines.peek().map(|next| next.trim()) {
+            if !trimmed.is_empty() && !is_block_start(trimmed) {
+                let level = match next.chars().next() {
+                    Some('=') if next.chars().all(|c| c == '=') => Some(1),
+                    Some('-') if next.chars().all(|c| c == '-') && builder.paragraph.is_empty() => {
+                        Some(2)
+                    }
+                    _ => None,
+                };
+
+                if let Some(level) = level {
+                    lines.next();
+                    builder.flush();
+                    builder.push_inline(trimmed, |text| Line::heading(text, level));
+                    continue;
+                }
+            }
+        }
+
+        // gemtext has no rules, a blank line is the closest thing
+        if trimmed.is_empty() || is_rule(trimmed) {
+            builder.flush();
+            builder.blank();
+        } else if let Some((level, heading)) = heading(trimmed) {
+            builder.flush();
+            builder.push_inline(heading, |text| Line::heading(text, level));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            builder.flush();
+            builder.push_inline(quote.trim(), Line::quote);
+        } else if let Some(item) = unordered_item(trimmed) {
+            builder.flush();
+            builder.push_inline(item, Line::unordered_list_item);
+        } else if is_ordered_item(trimmed) {
+            builder.flush();
+            builder.push_inline(trimmed, Line::text);
+        } else if let Some(link) = reference_definition(trimmed, url) {
+            builder.flush();
+            builder.push(link);
+        } else {
+            builder.paragraph.push(trimmed.to_string());
+        }
+    }
+
+    builder.flush();
+    builder.finish()
+}
+
+struct Builder<'a> {
+    base_url: &'a Url,
+    lines: Vec<Line>,
+    paragraph: Vec<String>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(base_url: &'a Url) -> Self {
+        Self {
+            base_url,
+            lines: vec![],
+            paragraph: vec![],
+        }
+    }
+
+    fn push(&mut self, line: Line) {
+        self.lines.push(line);
+    }
+
+    fn push_inline(&mut self, text: &str, line: impl Fn(&str) -> Line) {
+        let (text, links) = inline(text, self.base_url);
+
+        self.lines.push(line(&text));
+        self.lines.extend(links);
+    }
+
+    // soft wrapped paragraph lines become a single text line
+    fn flush(&mut self) {
+        if self.paragraph.is_empty() {
+            return;
+        }
+
+        let paragraph = self.paragraph.join(" ");
+        self.paragraph.clear();
+
+        self.push_inline(&paragraph, Line::text);
+    }
+
+    // runs of blank lines collapse into one, and the document never starts with one
+    fn blank(&mut self) {
+        if !self.lines.is_empty() && !self.ends_blank() {
+            self.lines.push(Line::text(""));
+        }
+    }
+
+    fn ends_blank(&self) -> bool {
+        matches!(self.lines.last(), Some(Line::Text { content }) if content.is_empty())
+    }
+
+    fn finish(mut self) -> Document {
+        if self.ends_blank() {
+            self.lines.pop();
+        }
+
+        Document::new(self.lines)
+    }
+}
+
+fn is_block_start(line: &str) -> bool {
+    heading(line).is_some()
+        || line.starts_with('>')
+        || unordered_item(line).is_some()
+        || is_ordered_item(line)
+        || is_rule(line)
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+
+    if level == 0 || level > 6 || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+
+    // gemtext only has three heading levels
+    Some((level.min(3), rest.trim().trim_end_matches('#').trim_end()))
+}
+
+fn unordered_item(line: &str) -> Option<&str> {
+    ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+        .map(str::trim)
+}
+
+fn is_ordered_item(line: &str) -> bool {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+
+    digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") "))
+}
+
+fn is_rule(line: &str) -> bool {
+    let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+
+    line.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&marker| line.chars().all(|c| c == marker))
+}
+
+// [label]: https://example.org
+fn reference_definition(line: &str, base_url: &Url) -> Option<Line> {
+    let rest = line.strip_prefix('[')?;
+    let (label, target) = rest.split_once("]:")?;
+    let target = target.split_whitespace().next()?;
+
+    let url = base_url
+        .join(target.trim_matches(|c| c == '<' || c == '>'))
+        .ok()?;
+
+    Some(Line::link(url, Some(label)))
+}
+
+// strips inline markup and collects [text](url), ![alt](src) and <url> links
+fn inline(text: &str, base_url: &Url) -> (String, Vec<Line>) {
+    let mut output = String::new();
+    let mut links = vec![];
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['[', '<']) {
+        let (before, candidate) = rest.split_at(start);
+
+        if let Some(candidate) = candidate.strip_prefix('<') {
+            if let Some((target, after)) = candidate.split_once('>') {
+                if let Some(url) = Url::parse(target).ok().filter(|_| target.contains("://")) {
+                    output.push_str(before);
+                    output.push_str(target);
+                    links.push(Line::link(url, None));
+                    rest = after;
+                    continue;
+                }
+            }
+        } else if let Some((label, after)) = candidate[1..].split_once("](") {
+            if let Some((target, after)) = after.split_once(')') {
+                let before = before.strip_suffix('!').unwrap_or(before);
+                let target = target.split_whitespace().next().unwrap_or_default();
+
+                output.push_str(before);
+                output.push_str(label);
+
+                if let Ok(url) = base_url.join(target) {
+                    links.push(Line::link(url, Some(label).filter(|l| !l.is_empty())));
+                }
+
+                rest = after;
+                continue;
+            }
+        }
+
+        output.push_str(before);
+        output.push_str(&candidate[..1]);
+        rest = &candidate[1..];
+    }
+
+    output.push_str(rest);
+
+    let output = ["**", "__", "`"]
+        .iter()
+        .fold(output, |output, marker| output.replace(marker, ""));
+
+    (output, links)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build(input: &str) -> Vec<Line> {
+        let url = Url::parse("gemini://example.org/docs/readme.md").unwrap();
+
+        build_markdown_document(input.as_bytes(), &url)
+            .lines()
+            .clone()
+    }
+
+    fn link(url: &str, name: Option<&str>) -> Line {
+        Line::link(url.parse().unwrap(), name)
+    }
+
+    #[test]
+    fn test_headings() {
+        assert_eq!(
+            vec![
+                Line::heading("Title", 1),
+                Line::heading("Section", 2),
+                Line::heading("Deep", 3),
+                Line::heading("Setext", 1),
+                Line::heading("Underlined", 2),
+            ],
+            build("# Title\n## Section ##\n##### Deep\nSetext\n===\nUnderlined\n---")
+        );
+    }
+
+    #[test]
+    fn test_paragraphs_and_lists() {
+        assert_eq!(
+            vec![
+                Line::text("Wrapped paragraph with bold text."),
+                Line::text(""),
+                Line::unordered_list_item("one"),
+                Line::unordered_list_item("two"),
+                Line::text("1. first"),
+                Line::quote("quoted"),
+            ],
+            build("Wrapped paragraph\nwith **bold** text.\n\n- one\n* two\n1. first\n> quoted")
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        assert_eq!(
+            vec![
+                Line::text("See the guide and a cat or https://example.com/."),
+                link("gemini://example.org/docs/guide.md", Some("the guide")),
+                link("gemini://example.org/cat.png", Some("a cat")),
+                link("https://example.com/", None),
+                link("gemini://example.org/ref", Some("ref")),
+            ],
+            build(
+                "See [the guide](guide.md) and ![a cat](/cat.png) or <https://example.com/>.\n\
+                 [ref]: /ref"
+            )
+        );
+    }
+
+    #[test]
+    fn test_code_fences() {
+        assert_eq!(
+            vec![
+                Line::text("Code:"),
+                Line::preformatted(
+                    Some("rust"),
+                    vec![Line::text("# not a heading"), Line::text("- not a list")]
+                ),
+                Line::text("After"),
+            ],
+            build("Code:\n```rust\n# not a heading\n- not a list\n```\nAfter")
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use mime::Mime;
+
+pub fn gemini() -> Mime {
+    "text/gemini".parse().unwrap()
+}
+
+pub fn from_path(path: &str) -> Mime {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+
+    let mime = match extension.as_deref() {
+        Some("gmi" | "gemini") => "text/gemini",
+        Some("txt" | "text") | None => "text/plain",
+        Some("md" | "markdown") => "text/markdown",
+        Some("html" | "htm") => "text/html",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        Some("gz" | "tgz") => "application/gzip",
+        Some("tar") => "application/x-tar",
+        Some("mp3") => "audio/mpeg",
+        Some("ogg") => "audio/ogg",
+        Some(_) => "application/octet-stream",
+    };
+
+    mime.parse().unwrap()
+}
+
+pub fn extension(mime: &Mime) -> Option<&'static str> {
+    let extension = match mime.essence_str() {
+        "text/gemini" => "gmi",
+        "text/plain" => "txt",
+        "text/markdown" => "md",
+        "text/html" => "html",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "application/pdf" => "pdf",
+        "application/zip" => "zip",
+        "application/gzip" => "gz",
+        "application/x-tar" => "tar",
+        "audio/mpeg" => "mp3",
+        "audio/ogg" => "ogg",
+        _ => return None,
+    };
+
+    Some(extension)
+}
+
+pub fn is_text(mime: &Mime) -> bool {
+    mime.type_() == mime::TEXT
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!("text/gemini", from_path("/notes/index.gmi").essence_str());
+        assert_eq!("image/png", from_path("/images/Cat.PNG").essence_str());
+        assert_eq!("text/plain", from_path("/about").essence_str());
+        assert_eq!(
+            "application/octet-stream",
+            from_path("/files/archive.7z").essence_str()
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use std::io;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+
+use anyhow::anyhow;
+use url::Url;
+
+pub fn get_tcp_stream(url: &Url, default_port: u16) -> anyhow::Result<TcpStream> {
+    let (_, addr) = url_to_socket_addrs(url, default_port)?;
+
+    Ok(TcpStream::connect(addr)?)
+}
+
+pub fn url_to_socket_addrs(url: &Url, default_port: u16) -> anyhow::Result<(&str, SocketAddr)> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("could not extract host from url"))?;
+
+    let port = url.port().unwrap_or(default_port);
+
+    // lookup failures are reported like any other host that can't be reached
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| io::Error::new(io::ErrorKind::HostUnreachable, e))?
+        .next()
+        .ok_or_else(|| anyhow!("failed to create SocketAddr"))?;
+
+    Ok((host, addrs))
+}
@@ -0,0 +1,64 @@
+use std::io::Write;
+
+use log::info;
+use url::Url;
+
+use crate::header::{Header, Status};
+use crate::mime_types;
+use crate::net::get_tcp_stream;
+use crate::response::Response;
+use crate::transfer::{read_body, BodyPolicy};
+
+pub const NEX_SCHEME: &str = "nex";
+
+const DEFAULT_NEX_PORT: u16 = 1900;
+
+#[derive(Debug)]
+pub struct NexClient {
+    body_policy: BodyPolicy,
+}
+
+impl NexClient {
+    pub fn new(body_policy: BodyPolicy) -> Self {
+        Self { body_policy }
+    }
+
+    pub fn get(&self, url: &Url) -> anyhow::Result<Response> {
+        info!("getting nex url: {}", url.to_string());
+
+        let mut stream = get_tcp_stream(url, DEFAULT_NEX_PORT)?;
+
+        stream.write_all(format!("{}\n", url.path()).as_bytes())?;
+        stream.flush()?;
+
+        let mime = if is_directory(url) {
+            mime_types::gemini()
+        } else {
+            mime_types::from_path(url.path())
+        };
+
+        let body = read_body(stream, url, &mime, &self.body_policy)?;
+        let header = Header::success(Status::Success, mime);
+
+        Ok(Response::from_body(header, body, url))
+    }
+}
+
+pub fn is_directory(url: &Url) -> bool {
+    url.path().is_empty() || url.path().ends_with('/')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_directory() {
+        assert!(is_directory(&"nex://example.org".parse().unwrap()));
+        assert!(is_directory(&"nex://example.org/".parse().unwrap()));
+        assert!(is_directory(&"nex://example.org/docs/".parse().unwrap()));
+        assert!(!is_directory(
+            &"nex://example.org/docs/notes.txt".parse().unwrap()
+        ));
+    }
+}
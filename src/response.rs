@@ -1,37 +1,105 @@
 #![allow(dead_code)]
+use std::io::{BufRead, BufReader, Read};
+
 use log::info;
+use mime::Mime;
 use url::Url;
 
-use crate::header::{build_header, Header};
+use crate::header::{build_header, Header, Inner, ParseMode};
+use crate::transfer::{read_body, Body, BodyPolicy, Transfer};
+
+// generous enough that an overlong meta reaches the parser and is reported as such
+const MAX_HEADER_LINE_LENGTH: u64 = 2048;
 
 #[derive(Debug, Clone)]
 pub struct Response {
     header: Header,
     body: Option<Vec<u8>>,
+    transfer: Option<Transfer>,
     url: Url,
 }
 
 impl Response {
-    pub fn parse(data: &[u8], url: &Url) -> anyhow::Result<Self> {
-        let (header, body) = build_header(data)?;
-
-        info!("parsed header: {}", &header);
-
-        Ok(Self {
+    pub fn new(header: Header, body: Option<Vec<u8>>, url: &Url) -> Self {
+        Self {
             header,
             body,
+            transfer: None,
             url: url.to_owned(),
-        })
+        }
+    }
+
+    pub fn from_body(header: Header, body: Body, url: &Url) -> Self {
+        match body {
+            Body::Buffered(buf) => Self::new(header, Some(buf), url),
+            Body::Streamed(transfer) => Self {
+                header,
+                body: None,
+                transfer: Some(transfer),
+                url: url.to_owned(),
+            },
+        }
+    }
+
+    pub fn parse(data: &[u8], url: &Url, mode: ParseMode) -> anyhow::Result<Self> {
+        let (header, body) = build_header(data, mode)?;
+
+        info!("parsed header: {}", &header);
+
+        Ok(Self::new(header, body, url))
+    }
+
+    // reads the header line, then hands the rest of the stream to the body policy
+    pub fn read<R: Read + Send + 'static>(
+        stream: R,
+        url: &Url,
+        mode: ParseMode,
+        policy: &BodyPolicy,
+    ) -> anyhow::Result<Self> {
+        let mut reader = BufReader::new(stream);
+
+        let mut line = vec![];
+        (&mut reader)
+            .take(MAX_HEADER_LINE_LENGTH)
+            .read_until(b'\n', &mut line)?;
+
+        let (header, _) = build_header(&line, mode)?;
+
+        info!("read header: {}", &header);
+
+        let body = match header.inner() {
+            Inner::Success { mime } => read_body(reader, url, mime, policy)?,
+            _ => Body::Buffered(vec![]),
+        };
+
+        Ok(Self::from_body(header, body, url))
     }
 
     pub fn body(&self) -> Option<&Vec<u8>> {
         self.body.as_ref()
     }
 
+    pub fn transfer(&self) -> Option<&Transfer> {
+        self.transfer.as_ref()
+    }
+
     pub fn header(&self) -> &Header {
         &self.header
     }
 
+    pub fn mime(&self) -> Option<&Mime> {
+        match self.header.inner() {
+            Inner::Success { mime } => Some(mime),
+            _ => None,
+        }
+    }
+
+    pub fn lang(&self) -> Option<String> {
+        self.mime()
+            .and_then(|mime| mime.get_param("lang"))
+            .map(|lang| lang.to_string())
+    }
+
     pub fn url(&self) -> &Url {
         &self.url
     }
@@ -0,0 +1,204 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+use crate::header::{Header, Status};
+
+// servers choose how long a slow down lasts, but a bogus meta shouldn't stall us for days
+const MAX_SLOW_DOWN: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Copy, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            base_delay,
+            max_delay,
+        }
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    // how long the page counts down before trying again, None when it should stay as it is
+    pub fn delay(&self, header: &Header, attempt: u32) -> Option<Duration> {
+        match header.status() {
+            // the server said how long to wait, that is shown even once we stop retrying
+            Status::SlowDown => Some(
+                self.slow_down(header)
+                    .unwrap_or_else(|| self.backoff(attempt)),
+            ),
+            Status::TemporaryFailure | Status::ServerUnavailable if self.retries(attempt) => {
+                Some(self.backoff(attempt))
+            }
+            _ => None,
+        }
+    }
+
+    // whether the countdown after this attempt reloads the page by itself
+    pub fn retries(&self, attempt: u32) -> bool {
+        attempt + 1 < self.max_attempts
+    }
+
+    // the wait a 44 response asked for, None for every other response
+    pub fn slow_down(&self, header: &Header) -> Option<Duration> {
+        header
+            .slow_down_seconds()
+            .map(|seconds| Duration::from_secs(seconds).min(MAX_SLOW_DOWN))
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
+
+// remembers hosts that asked us to slow down, so later requests wait their turn
+#[derive(Debug, Default)]
+pub struct HostBackoff {
+    hosts: RefCell<HashMap<String, Instant>>,
+}
+
+impl HostBackoff {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, host: &str, delay: Duration) {
+        let until = match Instant::now().checked_add(delay) {
+            Some(until) => until,
+            None => {
+                warn!("ignoring slow down for {}, {:?} is too long", host, delay);
+                return;
+            }
+        };
+
+        let mut hosts = self.hosts.borrow_mut();
+        let entry = hosts.entry(host.to_string()).or_insert(until);
+
+        *entry = (*entry).max(until);
+    }
+
+    pub fn remaining(&self, host: &str) -> Option<Duration> {
+        let mut hosts = self.hosts.borrow_mut();
+        let remaining = hosts
+            .get(host)?
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero());
+
+        if remaining.is_none() {
+            hosts.remove(host);
+        }
+
+        remaining
+    }
+}
+
+// a request that was not sent because its host asked us to slow down
+#[derive(Debug)]
+pub struct BackingOff {
+    pub host: String,
+    pub remaining: Duration,
+}
+
+impl fmt::Display for BackingOff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} asked us to slow down, try again in {}s",
+            self.host,
+            self.remaining.as_secs() + 1
+        )
+    }
+}
+
+impl std::error::Error for BackingOff {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new(3, Duration::from_secs(1), Duration::from_secs(10))
+    }
+
+    #[test]
+    fn test_delay_slow_down() {
+        let header = Header::failure(Status::SlowDown, "5");
+
+        assert_eq!(Some(Duration::from_secs(5)), policy().delay(&header, 0));
+        assert_eq!(
+            Some(Duration::from_secs(60)),
+            policy().delay(&Header::failure(Status::SlowDown, "60"), 0)
+        );
+        assert_eq!(Some(Duration::from_secs(5)), policy().delay(&header, 2));
+    }
+
+    #[test]
+    fn test_retries() {
+        assert!(policy().retries(0));
+        assert!(policy().retries(1));
+        assert!(!policy().retries(2));
+    }
+
+    #[test]
+    fn test_delay_slow_down_is_capped() {
+        let header = Header::failure(Status::SlowDown, "18446744073709551615");
+
+        assert_eq!(Some(MAX_SLOW_DOWN), policy().delay(&header, 0));
+        assert_eq!(
+            Some(MAX_SLOW_DOWN),
+            policy().slow_down(&Header::failure(Status::SlowDown, "999999"))
+        );
+    }
+
+    #[test]
+    fn test_delay_backoff() {
+        let header = Header::failure(Status::ServerUnavailable, "");
+
+        assert_eq!(Some(Duration::from_secs(1)), policy().delay(&header, 0));
+        assert_eq!(Some(Duration::from_secs(2)), policy().delay(&header, 1));
+        assert_eq!(None, policy().delay(&header, 2));
+        assert_eq!(
+            None,
+            policy().delay(&Header::failure(Status::NotFound, ""), 0)
+        );
+    }
+
+    #[test]
+    fn test_host_backoff() {
+        let backoff = HostBackoff::new();
+
+        backoff.record("example.org", Duration::from_secs(30));
+        backoff.record("example.org", Duration::from_secs(1));
+        backoff.record("example.com", Duration::ZERO);
+
+        assert!(backoff.remaining("example.org").unwrap() > Duration::from_secs(20));
+        assert_eq!(None, backoff.remaining("example.com"));
+        assert_eq!(None, backoff.remaining("example.net"));
+    }
+
+    #[test]
+    fn test_host_backoff_too_long() {
+        let backoff = HostBackoff::new();
+
+        backoff.record("example.org", Duration::MAX);
+
+        assert_eq!(None, backoff.remaining("example.org"));
+    }
+}
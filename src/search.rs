@@ -0,0 +1,147 @@
+use std::fmt;
+use std::rc::Rc;
+
+use url::Url;
+
+use crate::db::model::SearchResult;
+use crate::db::Db;
+use crate::gemini::Document;
+
+pub struct SearchIndex {
+    db: Rc<Db>,
+}
+
+impl fmt::Debug for SearchIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SearchIndex")
+    }
+}
+
+impl SearchIndex {
+    pub fn new(db: Rc<Db>) -> Self {
+        Self { db }
+    }
+
+    pub fn index(&self, url: &Url, document: &Document) -> anyhow::Result<()> {
+        let title = document.title().unwrap_or(url.as_str());
+        let host = url.host_str().unwrap_or_default();
+
+        self.db
+            .index_page(url.as_str(), host, title, &document.text())
+    }
+
+    pub fn search(&self, input: &str, limit: u32) -> anyhow::Result<Vec<SearchResult>> {
+        let query = match fts_query(input) {
+            Some(query) => query,
+            None => return Ok(vec![]),
+        };
+
+        // snippets are shown on a single line
+        let results = self
+            .db
+            .search_pages(&query, limit)?
+            .into_iter()
+            .map(|result| SearchResult {
+                snippet: result.snippet.replace('\n', " "),
+                ..result
+            })
+            .collect();
+
+        Ok(results)
+    }
+}
+
+// every word has to appear, quoted so fts5 operators in the input are taken literally
+fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<_> = input
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::gemini::Line;
+
+    use super::*;
+
+    fn search_index() -> SearchIndex {
+        let db = Rc::new(Db::new(":memory:").unwrap());
+        db.prepare().unwrap();
+
+        SearchIndex::new(db)
+    }
+
+    fn document(title: &str, text: &str) -> Document {
+        Document::new(vec![Line::heading(title, 1), Line::text(text)])
+    }
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(None, fts_query("  "));
+        assert_eq!(
+            Some("\"tomato\" \"\"\"soup\" \"OR\"".to_string()),
+            fts_query("tomato \"soup OR")
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let index = search_index();
+        let soup: Url = "gemini://example.org/soup.gmi".parse().unwrap();
+        let bread: Url = "gemini://example.org/bread.gmi".parse().unwrap();
+
+        index
+            .index(&soup, &document("Soup", "A recipe for tomato soup"))
+            .unwrap();
+        index
+            .index(&bread, &document("Bread", "Best with tomato soup"))
+            .unwrap();
+        index
+            .index(&soup, &document("Soup", "A recipe for onion soup"))
+            .unwrap();
+
+        let results = index.search("tomato", 10).unwrap();
+
+        assert_eq!(1, results.len());
+        assert_eq!(bread.as_str(), results[0].url);
+        assert_eq!("Bread Best with tomato soup", results[0].snippet);
+
+        assert_eq!(2, index.search("SOUP", 10).unwrap().len());
+        assert!(index.search("soup\" OR", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_forgotten_pages_are_not_found() {
+        let index = search_index();
+        let soup: Url = "gemini://example.org/soup.gmi".parse().unwrap();
+        let bread: Url = "gemini://example.com/bread.gmi".parse().unwrap();
+
+        index
+            .index(&soup, &document("Soup", "A recipe for tomato soup"))
+            .unwrap();
+        index
+            .index(&bread, &document("Bread", "Best with tomato soup"))
+            .unwrap();
+
+        index.db.delete_history_host("example.org").unwrap();
+
+        let results = index.search("tomato", 10).unwrap();
+
+        assert_eq!(1, results.len());
+        assert_eq!(bread.as_str(), results[0].url);
+
+        index
+            .db
+            .clear_history(time::OffsetDateTime::now_utc() - time::Duration::hours(1))
+            .unwrap();
+
+        assert!(index.search("tomato", 10).unwrap().is_empty());
+    }
+}
@@ -1,9 +1,30 @@
+use std::time::Duration;
+
 use url::Url;
 
+use crate::download::default_open_command;
+use crate::handlers::HandlerTable;
+use crate::header::ParseMode;
+use crate::retry::RetryPolicy;
+use crate::transfer::BodyPolicy;
+
 #[derive(Debug, Clone)]
 pub struct Settings {
     default_url: Url,
     database_path: String,
+    download_directory: String,
+    max_document_size: u64,
+    open_folder_command: String,
+    handlers: HandlerTable,
+    inline_images: bool,
+    offline: bool,
+    max_image_dimension: u32,
+    max_attempts: u32,
+    max_retry_delay: u64,
+    cache_ttl: u64,
+    header_mode: ParseMode,
+    client_certificate_path: Option<String>,
+    client_key_path: Option<String>,
 }
 
 impl Settings {
@@ -11,6 +32,29 @@ impl Settings {
         Self {
             default_url: "gemini://gemini.conman.org".parse().unwrap(),
             database_path: "dioscuri.sqlite".to_string(),
+            download_directory: std::env::var("DIOSCURI_DOWNLOAD_DIRECTORY")
+                .unwrap_or_else(|_| "downloads".to_string()),
+            max_document_size: std::env::var("DIOSCURI_MAX_DOCUMENT_SIZE")
+                .ok()
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(8 * 1024 * 1024),
+            open_folder_command: std::env::var("DIOSCURI_OPEN_FOLDER_COMMAND")
+                .unwrap_or_else(|_| default_open_command().to_string()),
+            handlers: std::env::var("DIOSCURI_MAILCAP")
+                .map(|path| HandlerTable::load(&path))
+                .unwrap_or_default(),
+            inline_images: false,
+            offline: false,
+            max_image_dimension: 1024,
+            max_attempts: 3,
+            max_retry_delay: 10,
+            cache_ttl: std::env::var("DIOSCURI_CACHE_TTL")
+                .ok()
+                .and_then(|ttl| ttl.parse().ok())
+                .unwrap_or(60 * 60),
+            header_mode: ParseMode::Lenient,
+            client_certificate_path: std::env::var("DIOSCURI_CLIENT_CERTIFICATE").ok(),
+            client_key_path: std::env::var("DIOSCURI_CLIENT_KEY").ok(),
         }
     }
 
@@ -27,4 +71,58 @@ impl Settings {
     pub fn database_path(&self) -> String {
         self.database_path.clone()
     }
+
+    pub fn download_directory(&self) -> String {
+        self.download_directory.clone()
+    }
+
+    pub fn max_document_size(&self) -> u64 {
+        self.max_document_size
+    }
+
+    pub fn open_folder_command(&self) -> String {
+        self.open_folder_command.clone()
+    }
+
+    pub fn handlers(&self) -> HandlerTable {
+        self.handlers.clone()
+    }
+
+    pub fn inline_images(&self) -> bool {
+        self.inline_images
+    }
+
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    pub fn max_image_dimension(&self) -> u32 {
+        self.max_image_dimension
+    }
+
+    pub fn body_policy(&self) -> BodyPolicy {
+        BodyPolicy::new(self.max_document_size, &self.download_directory)
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new(
+            self.max_attempts,
+            Duration::from_secs(1),
+            Duration::from_secs(self.max_retry_delay),
+        )
+    }
+
+    pub fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.cache_ttl)
+    }
+
+    pub fn header_mode(&self) -> ParseMode {
+        self.header_mode
+    }
+
+    pub fn client_identity_paths(&self) -> Option<(String, String)> {
+        self.client_certificate_path
+            .clone()
+            .zip(self.client_key_path.clone())
+    }
 }
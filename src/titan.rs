@@ -0,0 +1,199 @@
+use std::fmt;
+use std::io::Write;
+use std::rc::Rc;
+
+use log::info;
+use mime::Mime;
+use native_tls::Identity;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use url::Url;
+
+use crate::header::ParseMode;
+use crate::response::Response;
+use crate::tls::verification::Verifier;
+use crate::tls::{build_connector, connect};
+use crate::transfer::BodyPolicy;
+
+const TITAN_SCHEME: &str = "titan";
+
+// characters that would end a parameter value or the path it sits in
+const PARAM: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b';')
+    .add(b'=')
+    .add(b'?')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`');
+
+// the mime keeps its slash, a token is opaque and a slash in it would start a new segment
+const TOKEN: &AsciiSet = &PARAM.add(b'/');
+
+#[derive(Debug, Clone)]
+pub struct Upload {
+    mime: Mime,
+    token: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Upload {
+    pub fn new(mime: Mime, token: Option<&str>, body: Vec<u8>) -> Self {
+        Self {
+            mime,
+            token: token.filter(|t| !t.is_empty()).map(str::to_string),
+            body,
+        }
+    }
+
+    pub fn mime(&self) -> &Mime {
+        &self.mime
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn size(&self) -> usize {
+        self.body.len()
+    }
+}
+
+pub struct TitanClient {
+    connector: native_tls::TlsConnector,
+    verifier: Rc<dyn Verifier>,
+    header_mode: ParseMode,
+    body_policy: BodyPolicy,
+}
+
+impl fmt::Debug for TitanClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TitanClient")
+    }
+}
+
+impl TitanClient {
+    pub fn new(
+        verifier: Rc<dyn Verifier>,
+        identity: Option<Identity>,
+        header_mode: ParseMode,
+        body_policy: BodyPolicy,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            connector: build_connector(identity)?,
+            verifier,
+            header_mode,
+            body_policy,
+        })
+    }
+
+    pub fn set_header_mode(&mut self, header_mode: ParseMode) {
+        self.header_mode = header_mode;
+    }
+
+    pub fn upload(&self, url: &Url, upload: &Upload) -> anyhow::Result<Response> {
+        let url = build_titan_url(url, upload)?;
+
+        info!(
+            "uploading {} bytes to url: {}",
+            upload.size(),
+            url.to_string()
+        );
+
+        let mut stream = connect(&self.connector, self.verifier.as_ref(), &url)?;
+
+        stream.write_all(format!("{}\r\n", url.as_str()).as_bytes())?;
+        stream.write_all(upload.body())?;
+        stream.flush()?;
+
+        Response::read(stream, &url, self.header_mode, &self.body_policy)
+    }
+}
+
+// titan parameters are appended to the path: titan://host/path;mime=x/y;size=n;token=t
+pub fn build_titan_url(url: &Url, upload: &Upload) -> anyhow::Result<Url> {
+    anyhow::ensure!(
+        url.scheme() == TITAN_SCHEME,
+        "upload url must use the titan scheme: {}",
+        url
+    );
+
+    let mut params = format!(
+        ";mime={};size={}",
+        utf8_percent_encode(upload.mime().as_ref(), PARAM),
+        upload.size()
+    );
+
+    if let Some(token) = upload.token() {
+        params.push_str(&format!(";token={}", utf8_percent_encode(token, TOKEN)));
+    }
+
+    let mut url = url.clone();
+    url.set_path(&format!("{}{}", url.path(), params));
+
+    Ok(url)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn upload(token: Option<&str>) -> Upload {
+        Upload::new(mime::TEXT_PLAIN, token, b"Hello Titan!".to_vec())
+    }
+
+    #[test]
+    fn test_build_titan_url() {
+        let url = Url::parse("titan://example.org/notes/hello.gmi").unwrap();
+
+        assert_eq!(
+            "titan://example.org/notes/hello.gmi;mime=text/plain;size=12",
+            build_titan_url(&url, &upload(None)).unwrap().as_str()
+        );
+    }
+
+    #[test]
+    fn test_build_titan_url_with_token() {
+        let url = Url::parse("titan://example.org/hello.gmi?q").unwrap();
+
+        assert_eq!(
+            "titan://example.org/hello.gmi;mime=text/plain;size=12;token=secret?q",
+            build_titan_url(&url, &upload(Some("secret")))
+                .unwrap()
+                .as_str()
+        );
+    }
+
+    #[test]
+    fn test_build_titan_url_encodes_params() {
+        let url = Url::parse("titan://example.org/hello.gmi").unwrap();
+        let upload = Upload::new(
+            "text/gemini; charset=utf-8; lang=en".parse().unwrap(),
+            Some("a;b=c/d?e#f%"),
+            b"Hello Titan!".to_vec(),
+        );
+
+        assert_eq!(
+            "titan://example.org/hello.gmi;mime=text/gemini%3B%20charset%3Dutf-8%3B%20lang%3Den;size=12;token=a%3Bb%3Dc%2Fd%3Fe%23f%25",
+            build_titan_url(&url, &upload).unwrap().as_str()
+        );
+    }
+
+    #[test]
+    fn test_build_titan_url_empty_token() {
+        assert_eq!(None, upload(Some("")).token());
+    }
+
+    #[test]
+    fn test_build_titan_url_wrong_scheme() {
+        let url = Url::parse("gemini://example.org/hello.gmi").unwrap();
+
+        assert!(build_titan_url(&url, &upload(None)).is_err());
+    }
+}
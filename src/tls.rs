@@ -1,45 +1,106 @@
-use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::fmt;
+use std::net::TcpStream;
 
 use anyhow::anyhow;
-use native_tls::{TlsConnector, TlsStream};
+use log::info;
+use native_tls::{Identity, TlsConnector, TlsStream};
 use url::Url;
 
-const DEFAULT_GEMINI_PORT: u16 = 1965;
+use crate::net::url_to_socket_addrs;
+use crate::tls::verification::{State, Verifier};
 
-pub fn build_connector() -> anyhow::Result<TlsConnector> {
-    TlsConnector::builder()
+pub const DEFAULT_GEMINI_PORT: u16 = 1965;
+
+pub fn build_connector(identity: Option<Identity>) -> anyhow::Result<TlsConnector> {
+    let mut builder = TlsConnector::builder();
+
+    builder
         .disable_built_in_roots(true)
-        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_certs(true);
+
+    if let Some(identity) = identity {
+        builder.identity(identity);
+    }
+
+    builder
         .build()
         .map_err(|_| anyhow!("failed to build connector"))
 }
 
-pub fn get_stream(connector: &TlsConnector, url: &Url) -> anyhow::Result<TlsStream<TcpStream>> {
-    let (host, addr) = url_to_socket_addrs(url)?;
-    let stream = TcpStream::connect(&addr)?;
+pub fn load_identity(certificate_path: &str, key_path: &str) -> anyhow::Result<Identity> {
+    info!("loading client identity from: {}", certificate_path);
 
-    connector
-        .connect(host, stream)
-        .map_err(|_| anyhow!("failed to connect to addr: {}", addr))
+    let certificate = std::fs::read(certificate_path)?;
+    let key = std::fs::read(key_path)?;
+
+    Identity::from_pkcs8(&certificate, &key)
+        .map_err(|_| anyhow!("failed to load client identity from pem files"))
+}
+
+// kept apart from connection failures so the ui can tell the user what went wrong
+#[derive(Debug, Clone, PartialEq)]
+pub enum TlsError {
+    Handshake(String),
+    Verification(String),
+    CertificateConflict(String),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsError::Handshake(error) => write!(f, "tls handshake failed: {}", error),
+            TlsError::Verification(error) => {
+                write!(f, "certificate verification failed: {}", error)
+            }
+            TlsError::CertificateConflict(host) => write!(
+                f,
+                "the certificate for {} does not match the one seen before",
+                host
+            ),
+        }
+    }
 }
 
-fn url_to_socket_addrs(url: &Url) -> anyhow::Result<(&str, SocketAddr)> {
-    let host = url
-        .host_str()
-        .ok_or_else(|| anyhow!("could not extract host from url"))?;
+impl std::error::Error for TlsError {}
+
+pub fn connect(
+    connector: &TlsConnector,
+    verifier: &dyn Verifier,
+    url: &Url,
+) -> anyhow::Result<TlsStream<TcpStream>> {
+    let stream = get_stream(connector, url)?;
 
-    let port = url.port().unwrap_or(DEFAULT_GEMINI_PORT);
+    let certificate = stream
+        .peer_certificate()
+        .map_err(|e| TlsError::Handshake(e.to_string()))?;
 
-    let addrs = (host, port)
-        .to_socket_addrs()?
-        .next()
-        .ok_or_else(|| anyhow!("failed to create SocketAddr"))?;
+    let certificate_status = verifier
+        .verify(certificate.as_ref(), url)
+        .map_err(|e| TlsError::Verification(e.to_string()))?;
+    info!("TOFU certificate status: {}", certificate_status);
+
+    // TODO: need to make it possible for a user to respond to this event in the UI
+    if State::Conflict == certificate_status {
+        let host = url.host_str().unwrap_or_default().to_string();
+
+        return Err(TlsError::CertificateConflict(host).into());
+    }
 
-    Ok((host, addrs))
+    Ok(stream)
+}
+
+pub fn get_stream(connector: &TlsConnector, url: &Url) -> anyhow::Result<TlsStream<TcpStream>> {
+    let (host, addr) = url_to_socket_addrs(url, DEFAULT_GEMINI_PORT)?;
+    let stream = TcpStream::connect(addr)?;
+
+    connector
+        .connect(host, stream)
+        .map_err(|e| TlsError::Handshake(format!("{}: {}", addr, e)).into())
 }
 
 pub mod verification {
     use std::fmt;
+    use std::rc::Rc;
 
     use anyhow::anyhow;
     use native_tls::Certificate;
@@ -66,11 +127,11 @@ pub mod verification {
     }
 
     pub struct TofuVerifier {
-        db: Db,
+        db: Rc<Db>,
     }
 
     impl TofuVerifier {
-        pub fn new(db: Db) -> Self {
+        pub fn new(db: Rc<Db>) -> Self {
             Self { db }
         }
     }
@@ -129,11 +190,11 @@ pub mod verification {
         certificate
             .validity()
             .is_valid()
-            .then(|| {})
+            .then_some(())
             .ok_or_else(|| anyhow!("failed to validate certificate using time range validity"))
     }
 
-    fn dns_name_from_url(url: &Url) -> anyhow::Result<webpki::DnsNameRef> {
+    fn dns_name_from_url(url: &Url) -> anyhow::Result<webpki::DnsNameRef<'_>> {
         webpki::DnsNameRef::try_from_ascii_str(
             url.host_str()
                 .ok_or_else(|| anyhow!("failed to convert url to ascii string"))?,
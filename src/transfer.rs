@@ -0,0 +1,292 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use log::{info, warn};
+use mime::Mime;
+use url::Url;
+
+use crate::download::{create_unique_file, infer_file_name};
+use crate::images;
+use crate::mime_types;
+
+const CHUNK_SIZE: usize = 16 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct BodyPolicy {
+    max_document_size: u64,
+    download_directory: PathBuf,
+}
+
+impl BodyPolicy {
+    pub fn new(max_document_size: u64, download_directory: impl Into<PathBuf>) -> Self {
+        Self {
+            max_document_size,
+            download_directory: download_directory.into(),
+        }
+    }
+
+    pub fn max_document_size(&self) -> u64 {
+        self.max_document_size
+    }
+
+    pub fn create_download_file(&self, url: &Url, mime: &Mime) -> io::Result<(PathBuf, File)> {
+        create_unique_file(&self.download_directory, &infer_file_name(url, Some(mime)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferState {
+    Running,
+    Finished,
+    Failed(String),
+}
+
+impl fmt::Display for TransferState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferState::Running => write!(f, "running"),
+            TransferState::Finished => write!(f, "finished"),
+            TransferState::Failed(error) => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+// a body being written to disk by a background thread, shared with the ui for progress
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    url: Url,
+    path: PathBuf,
+    received: Arc<AtomicU64>,
+    state: Arc<Mutex<TransferState>>,
+}
+
+impl Transfer {
+    pub fn spawn<R: Read + Send + 'static>(
+        url: &Url,
+        path: &Path,
+        file: File,
+        prefix: Vec<u8>,
+        reader: R,
+    ) -> anyhow::Result<Self> {
+        info!("streaming {} to: {}", url, path.display());
+
+        let transfer = Self {
+            url: url.clone(),
+            path: path.to_path_buf(),
+            received: Arc::new(AtomicU64::new(0)),
+            state: Arc::new(Mutex::new(TransferState::Running)),
+        };
+
+        let worker = transfer.clone();
+
+        thread::spawn(move || {
+            let state = match worker.copy(prefix, reader, file) {
+                Ok(()) => TransferState::Finished,
+                Err(e) => {
+                    warn!("transfer of {} failed: {}", worker.url, e);
+                    TransferState::Failed(e.to_string())
+                }
+            };
+
+            *worker.state.lock().unwrap() = state;
+        });
+
+        Ok(transfer)
+    }
+
+    fn copy(&self, prefix: Vec<u8>, mut reader: impl Read, mut file: File) -> io::Result<()> {
+        file.write_all(&prefix)?;
+        self.received
+            .fetch_add(prefix.len() as u64, Ordering::Relaxed);
+
+        let mut buf = [0; CHUNK_SIZE];
+
+        loop {
+            let read = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            file.write_all(&buf[..read])?;
+            self.received.fetch_add(read as u64, Ordering::Relaxed);
+        }
+
+        file.flush()
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    pub fn state(&self) -> TransferState {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state() == TransferState::Running
+    }
+}
+
+#[derive(Debug)]
+pub enum Body {
+    Buffered(Vec<u8>),
+    Streamed(Transfer),
+}
+
+// text and image bodies up to the policy maximum are kept in memory for rendering,
+// everything else goes straight to disk
+pub fn read_body<R: Read + Send + 'static>(
+    mut reader: R,
+    url: &Url,
+    mime: &Mime,
+    policy: &BodyPolicy,
+) -> anyhow::Result<Body> {
+    let mut prefix = vec![];
+
+    if mime_types::is_text(mime) || images::is_supported(mime) {
+        reader
+            .by_ref()
+            .take(policy.max_document_size() + 1)
+            .read_to_end(&mut prefix)?;
+
+        if prefix.len() as u64 <= policy.max_document_size() {
+            return Ok(Body::Buffered(prefix));
+        }
+
+        info!(
+            "body of {} exceeds {} bytes, streaming to disk",
+            url,
+            policy.max_document_size()
+        );
+    }
+
+    let (path, file) = policy.create_download_file(url, mime)?;
+
+    Ok(Body::Streamed(Transfer::spawn(
+        url, &path, file, prefix, reader,
+    )?))
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use super::*;
+
+    // every test gets a directory of its own, emptied of whatever an earlier run left
+    fn policy(name: &str) -> BodyPolicy {
+        let directory = std::env::temp_dir()
+            .join(format!("dioscuri-test-{}", std::process::id()))
+            .join(name);
+        let _ = std::fs::remove_dir_all(&directory);
+
+        BodyPolicy::new(8, directory)
+    }
+
+    fn wait(transfer: &Transfer) -> TransferState {
+        while transfer.is_running() {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        transfer.state()
+    }
+
+    #[test]
+    fn test_read_body_buffers_small_text() {
+        let url = "gemini://example.org/small.gmi".parse().unwrap();
+        let body = read_body(
+            Cursor::new(b"# Hi".to_vec()),
+            &url,
+            &mime_types::gemini(),
+            &policy("small"),
+        )
+        .unwrap();
+
+        assert!(matches!(body, Body::Buffered(buf) if buf == b"# Hi"));
+    }
+
+    #[test]
+    fn test_read_body_streams_large_text() {
+        let url = "gemini://example.org/large.gmi".parse().unwrap();
+        let input = b"# A document longer than eight bytes".to_vec();
+
+        let body = read_body(
+            Cursor::new(input.clone()),
+            &url,
+            &mime_types::gemini(),
+            &policy("large"),
+        )
+        .unwrap();
+
+        match body {
+            Body::Streamed(transfer) => {
+                assert_eq!(TransferState::Finished, wait(&transfer));
+                assert_eq!(input.len() as u64, transfer.received());
+                assert_eq!(input, std::fs::read(transfer.path()).unwrap());
+            }
+            Body::Buffered(_) => panic!("expected a streamed body"),
+        }
+    }
+
+    #[test]
+    fn test_read_body_streams_binary() {
+        let url = "gemini://example.org/docs/paper.pdf".parse().unwrap();
+
+        let body = read_body(
+            Cursor::new(vec![0x89, b'P']),
+            &url,
+            &mime::APPLICATION_PDF,
+            &policy("binary"),
+        )
+        .unwrap();
+
+        match body {
+            Body::Streamed(transfer) => {
+                assert_eq!(TransferState::Finished, wait(&transfer));
+                assert!(transfer.path().ends_with("binary/paper.pdf"));
+            }
+            Body::Buffered(_) => panic!("expected a streamed body"),
+        }
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!("512 B", format_size(512));
+        assert_eq!("1.5 KiB", format_size(1536));
+        assert_eq!("2.0 MiB", format_size(2 * 1024 * 1024));
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+
+use eframe::egui;
+use log::warn;
+use url::Url;
+
+use crate::bookmarks;
+use crate::db::model::Bookmark;
+use crate::db::Db;
+use crate::event::{Event, EventBroadcaster, EventReceiver};
+
+// the bookmark being edited, kept apart so typing doesn't touch the list
+struct Editor {
+    id: i64,
+    title: String,
+    tags: String,
+    folder: String,
+}
+
+pub struct BookmarksWindow {
+    open: bool,
+    db: Rc<Db>,
+    bookmarks: Vec<Bookmark>,
+    editor: Option<Editor>,
+    dragging: Option<usize>,
+    changed: bool,
+    path: String,
+    notice: Option<String>,
+    error: Option<String>,
+    event_broadcaster: EventBroadcaster,
+    event_receiver: EventReceiver,
+}
+
+impl fmt::Debug for BookmarksWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BookmarksWindow")
+    }
+}
+
+impl BookmarksWindow {
+    pub fn new(
+        db: Rc<Db>,
+        event_broadcaster: EventBroadcaster,
+        event_receiver: EventReceiver,
+    ) -> Self {
+        let mut window = Self {
+            open: false,
+            db,
+            bookmarks: vec![],
+            editor: None,
+            dragging: None,
+            changed: false,
+            path: String::new(),
+            notice: None,
+            error: None,
+            event_broadcaster,
+            event_receiver,
+        };
+
+        window.refresh();
+
+        window
+    }
+
+    pub fn is_bookmarked(&self, url: &Url) -> bool {
+        self.bookmarks
+            .iter()
+            .any(|bookmark| bookmark.url == url.as_str())
+    }
+
+    // the star button adds the page, or removes it when it was already bookmarked
+    pub fn toggle(&mut self, url: &Url, title: Option<&str>) {
+        let existing = self
+            .bookmarks
+            .iter()
+            .find(|bookmark| bookmark.url == url.as_str());
+
+        let result = match existing {
+            Some(bookmark) => self.db.delete_bookmark(bookmark.id),
+            None => self
+                .db
+                .insert_bookmark(url.as_str(), title.unwrap_or(url.as_str()), "", ""),
+        };
+
+        if let Err(e) = result {
+            warn!("failed to toggle bookmark for {}: {}", url, e);
+        }
+
+        self.refresh();
+    }
+
+    // true once after the bookmarks were edited from the window
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    fn refresh(&mut self) {
+        match self.db.get_bookmarks() {
+            Ok(bookmarks) => self.bookmarks = bookmarks,
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context) {
+        let toggles = self
+            .event_receiver
+            .try_iter()
+            .filter(|event| matches!(event, Event::ShowBookmarks))
+            .count();
+
+        if toggles % 2 == 1 {
+            self.open = !self.open;
+            self.notice = None;
+            self.error = None;
+        }
+
+        let mut load = None;
+        let mut edit = None;
+        let mut drop_on = None;
+        let mut save = false;
+        let mut delete = false;
+        let mut import = false;
+        let mut export = false;
+
+        egui::Window::new("Bookmarks")
+            .open(&mut self.open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                if self.bookmarks.is_empty() {
+                    ui.label("No bookmarks have been saved yet.");
+                }
+
+                let mut folder = None;
+
+                for (index, bookmark) in self.bookmarks.iter().enumerate() {
+                    if folder != Some(&bookmark.folder) {
+                        let name = match bookmark.folder.as_str() {
+                            "" => "Unsorted",
+                            name => name,
+                        };
+
+                        ui.strong(name);
+                        folder = Some(&bookmark.folder);
+                    }
+
+                    let row = ui.horizontal(|ui| {
+                        let handle = ui
+                            .add(egui::Label::new("=").sense(egui::Sense::drag()))
+                            .on_hover_text("Drag to reorder");
+
+                        if handle.drag_started() {
+                            self.dragging = Some(index);
+                        }
+
+                        let link =
+                            egui::Label::new(egui::RichText::new(&bookmark.title).underline())
+                                .sense(egui::Sense::click());
+
+                        if ui.add(link).on_hover_text(&bookmark.url).clicked() {
+                            load = Some(bookmark.url.clone());
+                        }
+
+                        let tags = bookmark.tags();
+
+                        if !tags.is_empty() {
+                            ui.weak(tags.join(", "));
+                        }
+
+                        if ui.small_button("Edit").clicked() {
+                            edit = Some(index);
+                        }
+                    });
+
+                    let pointer = ui.input().pointer.hover_pos();
+
+                    // rows light up while something is dragged over them
+                    if self.dragging.is_some()
+                        && pointer.is_some_and(|pos| row.response.rect.contains(pos))
+                    {
+                        ui.painter().rect_stroke(
+                            row.response.rect,
+                            2.0,
+                            ui.visuals().selection.stroke,
+                        );
+
+                        if ui.input().pointer.any_released() {
+                            drop_on = Some(index);
+                        }
+                    }
+                }
+
+                if let Some(editor) = &mut self.editor {
+                    ui.separator();
+
+                    egui::Grid::new("bookmark_editor").show(ui, |ui| {
+                        ui.label("Title");
+                        ui.text_edit_singleline(&mut editor.title);
+                        ui.end_row();
+
+                        ui.label("Tags");
+                        ui.text_edit_singleline(&mut editor.tags);
+                        ui.end_row();
+
+                        ui.label("Folder");
+                        ui.text_edit_singleline(&mut editor.folder);
+                        ui.end_row();
+                    });
+
+                    ui.horizontal(|ui| {
+                        save = ui.button("Save").clicked();
+                        delete = ui.button("Delete").clicked();
+                    });
+                }
+
+                ui.separator();
+
+                // lagrange bookmarks.ini, amfora bookmarks.xml or av-98 bookmarks.gmi
+                ui.horizontal(|ui| {
+                    ui.label("File:");
+                    ui.text_edit_singleline(&mut self.path);
+                    import = ui.button("Import").clicked();
+                    export = ui.button("Export").clicked();
+                });
+
+                if let Some(notice) = &self.notice {
+                    ui.label(notice);
+                }
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+            });
+
+        if let Some(url) = load {
+            self.event_broadcaster.send(Event::load(&url)).unwrap();
+        }
+
+        if let Some(bookmark) = edit.and_then(|index| self.bookmarks.get(index)) {
+            self.editor = Some(Editor {
+                id: bookmark.id,
+                title: bookmark.title.clone(),
+                tags: bookmark.tags.clone(),
+                folder: bookmark.folder.clone(),
+            });
+        }
+
+        if let (Some(from), Some(to)) = (self.dragging, drop_on) {
+            self.reorder(from, to);
+        }
+
+        if ctx.input().pointer.any_released() {
+            self.dragging = None;
+        }
+
+        if save || delete {
+            self.finish_editing(delete);
+        }
+
+        if import || export {
+            let path = self.path.trim().to_string();
+
+            let result = if import {
+                self.import(Path::new(&path))
+                    .map(|count| format!("Imported {} bookmarks", count))
+            } else {
+                std::fs::write(&path, bookmarks::export(&self.bookmarks))
+                    .map(|_| format!("Exported {} bookmarks to {}", self.bookmarks.len(), path))
+                    .map_err(anyhow::Error::from)
+            };
+
+            match result {
+                Ok(notice) => {
+                    self.notice = Some(notice);
+                    self.error = None;
+                }
+                Err(e) => self.error = Some(e.to_string()),
+            }
+        }
+    }
+
+    // bookmarks that are already saved are left as they are
+    fn import(&mut self, path: &Path) -> anyhow::Result<usize> {
+        let mut saved: HashSet<String> = self.bookmarks.iter().map(|b| b.url.clone()).collect();
+        let mut count = 0;
+
+        for bookmark in bookmarks::import(path)? {
+            if !saved.insert(bookmark.url.clone()) {
+                continue;
+            }
+
+            self.db.insert_bookmark(
+                &bookmark.url,
+                &bookmark.title,
+                &bookmark.tags,
+                &bookmark.folder,
+            )?;
+
+            count += 1;
+        }
+
+        self.refresh();
+        self.changed = true;
+
+        Ok(count)
+    }
+
+    // the dragged bookmark takes the place, and the folder, of the one it was dropped on
+    fn reorder(&mut self, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+
+        let folder = self.bookmarks[to].folder.clone();
+
+        let mut bookmark = self.bookmarks.remove(from);
+        bookmark.folder = folder;
+        self.bookmarks.insert(to, bookmark);
+
+        if let Err(e) = self.db.move_bookmarks(&self.bookmarks) {
+            self.error = Some(e.to_string());
+        }
+
+        self.refresh();
+    }
+
+    fn finish_editing(&mut self, delete: bool) {
+        let editor = match self.editor.take() {
+            Some(editor) => editor,
+            None => return,
+        };
+
+        let result = if delete {
+            self.db.delete_bookmark(editor.id)
+        } else {
+            self.db.update_bookmark(
+                editor.id,
+                editor.title.trim(),
+                editor.tags.trim(),
+                editor.folder.trim(),
+            )
+        };
+
+        match result {
+            Ok(()) => self.changed = true,
+            Err(e) => self.error = Some(e.to_string()),
+        }
+
+        self.refresh();
+    }
+}
@@ -0,0 +1,286 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use eframe::egui;
+use log::warn;
+use mime::Mime;
+
+use crate::db::model::Download;
+use crate::db::Db;
+use crate::download::open_containing_folder;
+use crate::event::{Event, EventReceiver};
+use crate::handlers::{Action, Dispatcher};
+use crate::transfer::{format_size, Transfer, TransferState};
+
+struct ActiveDownload {
+    id: i64,
+    transfer: Transfer,
+    mime: Mime,
+    open_when_done: bool,
+}
+
+// an unanswered "what should happen with this file" question
+struct Prompt {
+    id: i64,
+    mime: Mime,
+    path: PathBuf,
+    remember: bool,
+}
+
+pub struct DownloadsWindow {
+    open: bool,
+    db: Rc<Db>,
+    dispatcher: Dispatcher,
+    active: Vec<ActiveDownload>,
+    prompts: Vec<Prompt>,
+    history: Vec<Download>,
+    open_folder_command: String,
+    error: Option<String>,
+    event_receiver: EventReceiver,
+}
+
+impl fmt::Debug for DownloadsWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DownloadsWindow")
+    }
+}
+
+impl DownloadsWindow {
+    pub fn new(
+        db: Rc<Db>,
+        dispatcher: Dispatcher,
+        open_folder_command: &str,
+        event_receiver: EventReceiver,
+    ) -> Self {
+        // nothing is running yet, so whatever the database says is running was cut off
+        if let Err(e) = db.interrupt_downloads() {
+            warn!("failed to mark interrupted downloads: {}", e);
+        }
+
+        let mut window = Self {
+            open: false,
+            db,
+            dispatcher,
+            active: vec![],
+            prompts: vec![],
+            history: vec![],
+            open_folder_command: open_folder_command.to_string(),
+            error: None,
+            event_receiver,
+        };
+
+        window.refresh_history();
+
+        window
+    }
+
+    pub fn add(&mut self, transfer: Transfer, mime: &Mime) {
+        let download = self.db.insert_download(
+            transfer.url().as_str(),
+            &transfer.path().display().to_string(),
+            mime.essence_str(),
+        );
+
+        let id = match download {
+            Ok(download) => download.id,
+            Err(e) => {
+                warn!("failed to record download: {}", e);
+                return;
+            }
+        };
+
+        let action = self.dispatcher.action_for(mime);
+
+        if action == Action::Ask {
+            self.prompts.push(Prompt {
+                id,
+                mime: mime.clone(),
+                path: transfer.path().to_path_buf(),
+                remember: false,
+            });
+        }
+
+        self.active.push(ActiveDownload {
+            id,
+            transfer,
+            mime: mime.clone(),
+            open_when_done: action == Action::Open,
+        });
+
+        self.refresh_history();
+        self.open = true;
+    }
+
+    fn refresh_history(&mut self) {
+        match self.db.get_downloads() {
+            Ok(history) => self.history = history,
+            Err(e) => warn!("failed to load download history: {}", e),
+        }
+    }
+
+    // records transfers whose background thread has finished since the last frame
+    fn poll(&mut self) {
+        let (running, done): (Vec<_>, Vec<_>) = self
+            .active
+            .drain(..)
+            .partition(|download| download.transfer.is_running());
+
+        self.active = running;
+
+        for download in &done {
+            let transfer = &download.transfer;
+            let state = transfer.state();
+
+            if let Err(e) =
+                self.db
+                    .finish_download(download.id, transfer.received(), &state.to_string())
+            {
+                warn!("failed to record finished download: {}", e);
+            }
+
+            if download.open_when_done && state == TransferState::Finished {
+                self.open_file(&download.mime, transfer.path());
+            }
+        }
+
+        if !done.is_empty() {
+            self.refresh_history();
+        }
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context) {
+        for event in self.event_receiver.try_iter() {
+            if let Event::ShowDownloads = event {
+                self.open = !self.open;
+            }
+        }
+
+        self.poll();
+
+        let mut open_folder = None;
+        let mut clear = false;
+        let mut answer = None;
+
+        egui::Window::new("Downloads")
+            .open(&mut self.open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                for (index, prompt) in self.prompts.iter_mut().enumerate() {
+                    let command = self.dispatcher.command_for(&prompt.mime).unwrap_or("-");
+
+                    ui.label(format!(
+                        "Open {} with: {}",
+                        file_name(&prompt.path.display().to_string()),
+                        command
+                    ));
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Open").clicked() {
+                            answer = Some((index, Action::Open));
+                        }
+
+                        if ui.button("Save").clicked() {
+                            answer = Some((index, Action::Save));
+                        }
+
+                        ui.checkbox(
+                            &mut prompt.remember,
+                            format!("Remember for {}", prompt.mime.essence_str()),
+                        );
+                    });
+
+                    ui.separator();
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for download in &self.history {
+                        let active = self.active.iter().find(|a| a.id == download.id);
+
+                        let (size, state) = match active {
+                            Some(active) => (active.transfer.received(), "downloading"),
+                            None => (download.size, download.state.as_str()),
+                        };
+
+                        ui.horizontal(|ui| {
+                            ui.label(file_name(&download.path))
+                                .on_hover_text(&download.url);
+                            ui.label(format!("{} ({})", format_size(size), state));
+
+                            if ui.small_button("Open folder").clicked() {
+                                open_folder = Some(download.path.clone());
+                            }
+                        });
+                    }
+                });
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                ui.separator();
+
+                if ui.button("Clear finished").clicked() {
+                    clear = true;
+                }
+            });
+
+        if let Some((index, action)) = answer {
+            self.answer(index, action);
+        }
+
+        if let Some(path) = open_folder {
+            self.error = open_containing_folder(&self.open_folder_command, Path::new(&path))
+                .err()
+                .map(|e| e.to_string());
+        }
+
+        if clear {
+            if let Err(e) = self.db.clear_downloads() {
+                warn!("failed to clear downloads: {}", e);
+            }
+
+            self.refresh_history();
+        }
+
+        // the counters move on background threads, keep painting until they settle
+        if !self.active.is_empty() {
+            ctx.request_repaint();
+        }
+    }
+}
+
+impl DownloadsWindow {
+    fn answer(&mut self, index: usize, action: Action) {
+        let prompt = self.prompts.remove(index);
+
+        if prompt.remember {
+            self.dispatcher.remember(&prompt.mime, action);
+        }
+
+        if action != Action::Open {
+            return;
+        }
+
+        // still downloading, open it once the transfer completes
+        match self.active.iter_mut().find(|a| a.id == prompt.id) {
+            Some(active) => active.open_when_done = true,
+            None => self.open_file(&prompt.mime, &prompt.path),
+        }
+    }
+
+    fn open_file(&mut self, mime: &Mime, path: &Path) {
+        self.error = self
+            .dispatcher
+            .open(mime, path)
+            .err()
+            .map(|e| e.to_string());
+    }
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().map_or_else(
+        || path.to_string(),
+        |name| name.to_string_lossy().to_string(),
+    )
+}
@@ -0,0 +1,177 @@
+use std::io;
+
+use url::Url;
+
+use crate::cache::NotCached;
+use crate::gemini::{Document, Line};
+use crate::header::{HeaderError, Inner, Status};
+use crate::response::Response;
+use crate::retry::BackingOff;
+use crate::tls::TlsError;
+
+// failure responses (4x, 5x and 6x) get a page explaining them instead of an empty document
+pub fn status_document(response: &Response) -> Option<Document> {
+    let header = response.header();
+
+    let (explanation, meta) = match header.inner() {
+        Inner::Failure { error } if is_permanent(header.status()) => (
+            "The server reported a permanent failure, trying again is unlikely to help.",
+            error,
+        ),
+        Inner::Failure { error } => (
+            "The server reported a temporary problem, trying again later may work.",
+            error,
+        ),
+        Inner::ClientCertificateRequired { error } => {
+            ("The page requires a client certificate.", error)
+        }
+        _ => return None,
+    };
+
+    Some(error_document(
+        status_title(header.status()),
+        explanation,
+        meta.as_deref(),
+        response.url(),
+    ))
+}
+
+// errors raised while fetching, before there was a response to look at
+pub fn fetch_error_document(url: &Url, error: &anyhow::Error) -> Document {
+    if let Some(error) = error.downcast_ref::<HeaderError>() {
+        return header_error_document(url, error);
+    }
+
+    if error.downcast_ref::<NotCached>().is_some() {
+        return error_document(
+            "Not available offline",
+            "This page has not been saved for offline reading. Go back online and try again.",
+            None,
+            url,
+        );
+    }
+
+    if let Some(error) = error.downcast_ref::<BackingOff>() {
+        return error_document(
+            "Slow down",
+            "The server asked us to wait before sending it more requests.",
+            Some(&error.to_string()),
+            url,
+        );
+    }
+
+    if let Some(error) = error.downcast_ref::<TlsError>() {
+        return error_document(
+            "Secure connection failed",
+            "The TLS connection to the server could not be established or trusted.",
+            Some(&error.to_string()),
+            url,
+        );
+    }
+
+    if error
+        .downcast_ref::<io::Error>()
+        .is_some_and(|error| is_connect_error(error.kind()))
+    {
+        return error_document(
+            "Connection failed",
+            "The server could not be reached.",
+            Some(&error.to_string()),
+            url,
+        );
+    }
+
+    error_document(
+        "Could not load page",
+        "Something went wrong while loading the page.",
+        Some(&error.to_string()),
+        url,
+    )
+}
+
+pub fn header_error_document(url: &Url, error: &HeaderError) -> Document {
+    error_document(
+        "Malformed response",
+        &format!("{} sent a response header that could not be parsed.", url),
+        Some(&error.to_string()),
+        url,
+    )
+}
+
+pub fn parse_error_document(url: &Url, error: &anyhow::Error) -> Document {
+    error_document(
+        "Could not display page",
+        "The response could not be turned into a document.",
+        Some(&error.to_string()),
+        url,
+    )
+}
+
+pub fn invalid_url_document(url: &str, error: &url::ParseError) -> Document {
+    Document::new(vec![
+        Line::heading("Invalid URL", 1),
+        Line::text(&format!("{} is not a valid URL.", url)),
+        Line::quote(&error.to_string()),
+    ])
+}
+
+fn error_document(title: &str, explanation: &str, detail: Option<&str>, url: &Url) -> Document {
+    let mut lines = vec![Line::heading(title, 1), Line::text(explanation)];
+
+    if let Some(detail) = detail {
+        lines.push(Line::quote(detail));
+    }
+
+    lines.push(Line::text(""));
+    lines.push(Line::link(url.clone(), Some("Retry")));
+
+    Document::new(lines)
+}
+
+// other io errors come from reading local files or writing downloads, not from the network
+fn is_connect_error(kind: io::ErrorKind) -> bool {
+    use io::ErrorKind::*;
+
+    matches!(
+        kind,
+        ConnectionRefused
+            | ConnectionReset
+            | ConnectionAborted
+            | NotConnected
+            | AddrNotAvailable
+            | HostUnreachable
+            | NetworkUnreachable
+            | TimedOut
+            | BrokenPipe
+    )
+}
+
+fn is_permanent(status: Status) -> bool {
+    use Status::*;
+
+    matches!(
+        status,
+        PermanentFailure | NotFound | Gone | ProxyRequestRefused | BadRequest
+    )
+}
+
+fn status_title(status: Status) -> &'static str {
+    use Status::*;
+
+    match status {
+        TemporaryFailure => "Temporary failure",
+        ServerUnavailable => "Server unavailable",
+        CgiError => "CGI error",
+        ProxyError => "Proxy error",
+        SlowDown => "Slow down",
+        PermanentFailure => "Permanent failure",
+        NotFound => "Not found",
+        Gone => "Gone",
+        ProxyRequestRefused => "Proxy request refused",
+        BadRequest => "Bad request",
+        ClientCertificateRequired => "Client certificate required",
+        CertificateNotAuthorized => "Certificate not authorized",
+        CertificateNotValid => "Certificate not valid",
+        _ => "Request failed",
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+
+use eframe::egui;
+use egui::{FontData, FontDefinitions, FontFamily};
+use log::{info, warn};
+
+use crate::language::Script;
+
+// the bundled egui fonts only cover latin, so other scripts fall back to system fonts
+fn candidates(script: Script) -> &'static [&'static str] {
+    match script {
+        Script::Arabic => &[
+            "/usr/share/fonts/truetype/noto/NotoSansArabic-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSansArabic-Regular.ttf",
+            "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        ],
+        Script::Hebrew => &[
+            "/usr/share/fonts/truetype/noto/NotoSansHebrew-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSansHebrew-Regular.ttf",
+            "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        ],
+        Script::Cyrillic | Script::Greek => &[
+            "/usr/share/fonts/truetype/noto/NotoSans-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSans-Regular.ttf",
+            "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        ],
+        Script::Devanagari => &[
+            "/usr/share/fonts/truetype/noto/NotoSansDevanagari-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSansDevanagari-Regular.ttf",
+        ],
+        Script::Thai => &[
+            "/usr/share/fonts/truetype/noto/NotoSansThai-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSansThai-Regular.ttf",
+        ],
+        Script::Cjk => &[
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+        ],
+        Script::Latin => &[],
+    }
+}
+
+#[derive(Debug)]
+pub struct FontFallbacks {
+    definitions: FontDefinitions,
+    attempted: HashSet<Script>,
+}
+
+impl FontFallbacks {
+    pub fn new() -> Self {
+        Self {
+            definitions: FontDefinitions::default(),
+            attempted: HashSet::new(),
+        }
+    }
+
+    pub fn ensure(&mut self, ctx: &egui::Context, script: Script) {
+        if !self.attempted.insert(script) {
+            return;
+        }
+
+        let loaded = candidates(script)
+            .iter()
+            .find_map(|path| std::fs::read(path).ok().map(|font| (path, font)));
+
+        let (path, font) = match loaded {
+            Some(loaded) => loaded,
+            None => {
+                warn!("no fallback font found for script: {:?}", script);
+                return;
+            }
+        };
+
+        info!("loading fallback font for {:?}: {}", script, path);
+
+        let name = format!("{:?}", script);
+
+        self.definitions
+            .font_data
+            .insert(name.clone(), FontData::from_owned(font));
+
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            self.definitions
+                .families
+                .entry(family)
+                .or_default()
+                .push(name.clone());
+        }
+
+        ctx.set_fonts(self.definitions.clone());
+    }
+}
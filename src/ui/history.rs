@@ -0,0 +1,213 @@
+use std::fmt;
+use std::rc::Rc;
+
+use eframe::egui;
+use log::warn;
+use time::{Duration, OffsetDateTime};
+use url::Url;
+
+use crate::db::model::HistoryEntry;
+use crate::db::Db;
+use crate::event::{Event, EventBroadcaster, EventReceiver};
+
+const RESULT_LIMIT: u32 = 200;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ClearRange {
+    LastHour,
+    LastDay,
+    LastWeek,
+    Everything,
+}
+
+impl ClearRange {
+    const ALL: [ClearRange; 4] = [
+        ClearRange::LastHour,
+        ClearRange::LastDay,
+        ClearRange::LastWeek,
+        ClearRange::Everything,
+    ];
+
+    fn since(&self) -> OffsetDateTime {
+        let now = OffsetDateTime::now_utc();
+
+        match self {
+            Self::LastHour => now - Duration::hours(1),
+            Self::LastDay => now - Duration::days(1),
+            Self::LastWeek => now - Duration::weeks(1),
+            Self::Everything => OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+}
+
+impl fmt::Display for ClearRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LastHour => write!(f, "Last hour"),
+            Self::LastDay => write!(f, "Last day"),
+            Self::LastWeek => write!(f, "Last week"),
+            Self::Everything => write!(f, "Everything"),
+        }
+    }
+}
+
+pub struct HistoryWindow {
+    open: bool,
+    db: Rc<Db>,
+    query: String,
+    range: ClearRange,
+    results: Vec<HistoryEntry>,
+    error: Option<String>,
+    event_broadcaster: EventBroadcaster,
+    event_receiver: EventReceiver,
+}
+
+impl fmt::Debug for HistoryWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HistoryWindow")
+    }
+}
+
+impl HistoryWindow {
+    pub fn new(
+        db: Rc<Db>,
+        event_broadcaster: EventBroadcaster,
+        event_receiver: EventReceiver,
+    ) -> Self {
+        Self {
+            open: false,
+            db,
+            query: String::new(),
+            range: ClearRange::LastHour,
+            results: vec![],
+            error: None,
+            event_broadcaster,
+            event_receiver,
+        }
+    }
+
+    pub fn record(&mut self, url: &Url, title: Option<&str>) {
+        let host = url.host_str().unwrap_or_default();
+
+        let now = OffsetDateTime::now_utc();
+
+        if let Err(e) = self.db.record_visit(url.as_str(), host, title, now) {
+            warn!("failed to record visit to {}: {}", url, e);
+        }
+
+        if self.open {
+            self.search();
+        }
+    }
+
+    fn search(&mut self) {
+        match self.db.search_history(self.query.trim(), RESULT_LIMIT) {
+            Ok(results) => self.results = results,
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context) {
+        let toggles = self
+            .event_receiver
+            .try_iter()
+            .filter(|event| matches!(event, Event::ShowHistory))
+            .count();
+
+        if toggles % 2 == 1 {
+            self.open = !self.open;
+            self.error = None;
+
+            if self.open {
+                self.search();
+            }
+        }
+
+        let mut searched = false;
+        let mut load = None;
+        let mut delete_host = None;
+        let mut clear = false;
+
+        egui::Window::new("History")
+            .open(&mut self.open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Search:");
+                    searched = ui.text_edit_singleline(&mut self.query).changed();
+                });
+
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        for entry in &self.results {
+                            ui.horizontal(|ui| {
+                                let title = entry.title.as_deref().unwrap_or(&entry.url);
+
+                                let link = egui::Label::new(egui::RichText::new(title).underline())
+                                    .sense(egui::Sense::click());
+
+                                if ui.add(link).on_hover_text(&entry.url).clicked() {
+                                    load = Some(entry.url.clone());
+                                }
+
+                                ui.label(format!(
+                                    "{} visits, {}",
+                                    entry.visits,
+                                    entry.visited.date()
+                                ));
+
+                                if !entry.host.is_empty()
+                                    && ui
+                                        .small_button("Delete host")
+                                        .on_hover_text(&entry.host)
+                                        .clicked()
+                                {
+                                    delete_host = Some(entry.host.clone());
+                                }
+                            });
+                        }
+                    });
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("history_clear_range")
+                        .selected_text(self.range.to_string())
+                        .show_ui(ui, |ui| {
+                            for range in ClearRange::ALL {
+                                ui.selectable_value(&mut self.range, range, range.to_string());
+                            }
+                        });
+
+                    if ui.button("Clear").clicked() {
+                        clear = true;
+                    }
+                });
+            });
+
+        if let Some(url) = load {
+            self.event_broadcaster.send(Event::load(&url)).unwrap();
+        }
+
+        let result = if let Some(host) = delete_host {
+            self.db.delete_history_host(&host).map(|_| true)
+        } else if clear {
+            self.db.clear_history(self.range.since()).map(|_| true)
+        } else {
+            Ok(searched)
+        };
+
+        match result {
+            Ok(true) => self.search(),
+            Ok(false) => {}
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+}
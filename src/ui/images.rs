@@ -0,0 +1,86 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use eframe::egui;
+
+use crate::images::DecodedImage;
+
+const MAX_CACHED_IMAGES: usize = 64;
+
+pub enum CachedImage {
+    Loading,
+    Ready(egui::TextureHandle),
+    Failed(String),
+}
+
+// textures keyed by url, uploaded lazily because that needs the egui context
+#[derive(Default)]
+pub struct ImageCache {
+    images: HashMap<String, CachedImage>,
+    pending: Vec<(String, DecodedImage)>,
+    order: VecDeque<String>,
+}
+
+impl fmt::Debug for ImageCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ImageCache({} images)", self.images.len())
+    }
+}
+
+impl ImageCache {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn contains(&self, url: &str) -> bool {
+        self.images.contains_key(url)
+    }
+
+    pub fn is_failed(&self, url: &str) -> bool {
+        matches!(self.images.get(url), Some(CachedImage::Failed(_)))
+    }
+
+    pub fn get(&self, url: &str) -> Option<&CachedImage> {
+        self.images.get(url)
+    }
+
+    pub fn set_loading(&mut self, url: &str) {
+        self.insert(url, CachedImage::Loading);
+    }
+
+    pub fn set_image(&mut self, url: &str, image: DecodedImage) {
+        self.insert(url, CachedImage::Loading);
+        self.pending.push((url.to_string(), image));
+    }
+
+    pub fn set_failed(&mut self, url: &str, error: &str) {
+        self.insert(url, CachedImage::Failed(error.to_string()));
+    }
+
+    pub fn upload(&mut self, ctx: &egui::Context) {
+        for (url, image) in self.pending.drain(..) {
+            // evicted before it was ever shown
+            if !self.images.contains_key(&url) {
+                continue;
+            }
+
+            let image = egui::ColorImage::from_rgba_unmultiplied(image.size(), image.rgba());
+            let texture = ctx.load_texture(&url, image);
+
+            self.images.insert(url, CachedImage::Ready(texture));
+        }
+    }
+
+    fn insert(&mut self, url: &str, image: CachedImage) {
+        if self.images.insert(url.to_string(), image).is_none() {
+            self.order.push_back(url.to_string());
+        }
+
+        // dropping a handle frees its texture
+        while self.order.len() > MAX_CACHED_IMAGES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.images.remove(&oldest);
+            }
+        }
+    }
+}
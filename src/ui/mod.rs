@@ -1,42 +1,187 @@
+mod bookmarks;
+mod downloads;
+mod error_pages;
+mod fonts;
 mod highlighter;
+mod history;
+mod images;
+mod page_info;
+mod search;
 mod session;
 mod toolbar;
+mod upload;
 mod viewport;
 
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
+use std::time::Duration;
+
+use anyhow::anyhow;
 use eframe::{egui, epi};
-use log::{debug, info};
+use log::{debug, error, info, warn};
+use mime::Mime;
+use time::OffsetDateTime;
 use url::Url;
 
-use crate::client::GeminiClient;
+use crate::about::ABOUT_SCHEME;
+use crate::cache::{self, NotCached, ResponseCache};
+use crate::charset;
+use crate::client::Clients;
+use crate::db::Db;
 use crate::event::{Event, EventBroadcaster, EventBus, EventReceiver};
-use crate::gemini::build_document;
+use crate::file::{self, FILE_SCHEME};
+use crate::finger::FINGER_SCHEME;
+use crate::gemini::{
+    build_document, build_link_list_document, build_preformatted_document, Document, Line,
+};
+use crate::handlers::Dispatcher;
+use crate::header::{Inner, Status};
+use crate::html::build_html_document;
+use crate::images::{self as image_types, DecodedImage};
+use crate::markdown::build_markdown_document;
+use crate::mime_types;
+use crate::nex::{self, NEX_SCHEME};
+use crate::response::Response;
+use crate::retry::{BackingOff, HostBackoff, RetryPolicy};
+use crate::search::SearchIndex;
 use crate::settings::Settings;
+use crate::titan::Upload;
+use crate::transfer::Transfer;
+use crate::ui::bookmarks::BookmarksWindow;
+use crate::ui::downloads::DownloadsWindow;
+use crate::ui::error_pages::{
+    fetch_error_document, invalid_url_document, parse_error_document, status_document,
+};
+use crate::ui::history::HistoryWindow;
+use crate::ui::page_info::{PageInfo, PageInfoWindow};
+use crate::ui::search::SearchWindow;
 use crate::ui::session::SessionHistory;
 use crate::ui::toolbar::Toolbar;
+use crate::ui::upload::UploadDialog;
 use crate::ui::viewport::Viewport;
+use crate::watcher::FileWatcher;
+
+// everything load needs to show a page, whether it rendered or failed
+struct LoadedPage {
+    url: Url,
+    document: Document,
+    info: PageInfo,
+    lang: Option<String>,
+    image: Option<Result<DecodedImage, String>>,
+    // the attempt the countdown loads the page with, how long it waits and if it fires by itself
+    retry: Option<(u32, Duration, bool)>,
+    cached: Option<OffsetDateTime>,
+}
+
+impl LoadedPage {
+    fn new(url: &Url, document: Document) -> Self {
+        Self {
+            url: url.clone(),
+            document,
+            info: PageInfo::new(url.as_str()),
+            lang: None,
+            image: None,
+            retry: None,
+            cached: None,
+        }
+    }
+}
+
+// a page being saved for offline reading, its links are fetched one per frame
+#[derive(Debug)]
+struct OfflineSave {
+    url: Url,
+    pending: VecDeque<Url>,
+    saved: usize,
+    total: usize,
+}
+
+impl OfflineSave {
+    fn progress(&self) -> String {
+        format!(
+            "Saving for offline reading: {} of {} pages saved, {} left",
+            self.saved,
+            self.total,
+            self.pending.len()
+        )
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "Saved {} of {} pages for offline reading",
+            self.saved, self.total
+        )
+    }
+}
 
 #[derive(Debug)]
 pub struct DioscuriApp {
     url: Option<Url>,
-    gemini_client: GeminiClient,
+    clients: Clients,
+    cache: ResponseCache,
+    offline: bool,
+    offline_save: Option<OfflineSave>,
+    retry_policy: RetryPolicy,
+    backoff: HostBackoff,
     event_bus: EventBus,
     event_broadcaster: EventBroadcaster,
     event_receiver: EventReceiver,
     settings: Settings,
     toolbar: Toolbar,
     viewport: Viewport,
+    upload_dialog: UploadDialog,
+    page_info: PageInfoWindow,
+    downloads: DownloadsWindow,
+    history: HistoryWindow,
+    bookmarks: BookmarksWindow,
+    search: SearchWindow,
     session_history: SessionHistory,
+    title: Option<String>,
+    file_watcher: Option<FileWatcher>,
 }
 
 impl DioscuriApp {
-    pub fn new(settings: Settings, mut event_bus: EventBus, gemini_client: GeminiClient) -> Self {
+    pub fn new(settings: Settings, mut event_bus: EventBus, clients: Clients, db: Rc<Db>) -> Self {
         let url = settings.default_url();
 
         let (broadcaster, receiver) = event_bus.subscribe();
-        let toolbar = Toolbar::new(broadcaster, receiver);
+        let toolbar = Toolbar::new(
+            broadcaster,
+            receiver,
+            settings.header_mode(),
+            settings.inline_images(),
+            settings.offline(),
+        );
 
         let broadcaster = event_bus.broadcaster();
-        let viewport = Viewport::new(Default::default(), broadcaster);
+        let mut viewport = Viewport::new(Default::default(), broadcaster);
+        viewport.set_inline_images(settings.inline_images());
+
+        let (broadcaster, receiver) = event_bus.subscribe();
+        let upload_dialog = UploadDialog::new(broadcaster, receiver);
+
+        let (_, receiver) = event_bus.subscribe();
+        let page_info = PageInfoWindow::new(receiver);
+
+        let cache = ResponseCache::new(db.clone(), settings.cache_ttl());
+
+        let (_, receiver) = event_bus.subscribe();
+        let dispatcher = Dispatcher::new(settings.handlers(), db.clone());
+        let downloads = DownloadsWindow::new(
+            db.clone(),
+            dispatcher,
+            &settings.open_folder_command(),
+            receiver,
+        );
+
+        let (broadcaster, receiver) = event_bus.subscribe();
+        let history = HistoryWindow::new(db.clone(), broadcaster, receiver);
+
+        let (broadcaster, receiver) = event_bus.subscribe();
+        let bookmarks = BookmarksWindow::new(db.clone(), broadcaster, receiver);
+
+        let (broadcaster, receiver) = event_bus.subscribe();
+        let search = SearchWindow::new(SearchIndex::new(db), broadcaster, receiver);
 
         let (event_broadcaster, event_receiver) = event_bus.subscribe();
 
@@ -48,14 +193,27 @@ impl DioscuriApp {
 
         Self {
             url,
-            gemini_client,
+            clients,
+            cache,
+            offline: settings.offline(),
+            offline_save: None,
+            retry_policy: settings.retry_policy(),
+            backoff: HostBackoff::new(),
             event_bus,
             event_broadcaster,
             event_receiver,
             settings,
             toolbar,
             viewport,
+            upload_dialog,
+            page_info,
+            downloads,
+            history,
+            bookmarks,
+            search,
             session_history,
+            title: None,
+            file_watcher: None,
         }
     }
 
@@ -63,8 +221,11 @@ impl DioscuriApp {
         debug!("processing events");
         self.event_bus.relay()?;
 
+        // collect first so the arms below are free to borrow self mutably
+        let events: Vec<Event> = self.event_receiver.try_iter().collect();
+
         // TODO: extract arm logic into functions
-        for event in self.event_receiver.try_iter() {
+        for event in events {
             match event {
                 Event::Back => {
                     info!("processing back event");
@@ -74,8 +235,7 @@ impl DioscuriApp {
                         info!("back - generating load event: {}", &page);
 
                         self.event_broadcaster
-                            .send(Event::load_dont_track(page.url()))
-                            .unwrap();
+                            .send(Event::load_from_cache(page.url()))?;
                     };
                 }
                 Event::Forward => {
@@ -86,35 +246,36 @@ impl DioscuriApp {
                         info!("forward - generating load event: {}", &page);
 
                         self.event_broadcaster
-                            .send(Event::load_dont_track(page.url()))
-                            .unwrap();
+                            .send(Event::load_from_cache(page.url()))?;
                     };
                 }
                 Event::Load {
                     url,
                     add_to_session,
+                    from_cache,
                 } => {
                     info!("processing load event for url: {}", url);
 
-                    let url: Url = url.parse().unwrap();
-                    self.url = Some(url.clone());
-
-                    let result = self.gemini_client.get(&url).unwrap();
-                    let document = build_document(result.body().unwrap(), &url).unwrap();
+                    self.load(&url, add_to_session, from_cache, 0);
+                }
+                Event::Retry { url, attempt } => {
+                    info!(
+                        "processing retry event for url: {} (attempt {})",
+                        url, attempt
+                    );
 
-                    self.viewport.set_document(document);
-                    self.toolbar.set_url(url.as_str());
+                    self.load(&url, false, false, attempt);
+                }
+                Event::LoadImage { url } => {
+                    info!("processing load image event for url: {}", url);
 
-                    if add_to_session {
-                        self.session_history.navigate(url.as_str());
-                    }
+                    self.load_image(&url);
                 }
                 Event::Home => {
                     info!("processing home event");
 
                     self.event_broadcaster
-                        .send(Event::load(&self.settings.default_url_as_string()))
-                        .unwrap();
+                        .send(Event::load(&self.settings.default_url_as_string()))?;
                 }
                 Event::Quit => {
                     info!("processing quit event");
@@ -124,17 +285,547 @@ impl DioscuriApp {
                 Event::Refresh => {
                     info!("processing refresh event");
 
-                    if self.url.is_some() {
-                        let url = self.url.as_ref().unwrap();
-
+                    if let Some(url) = &self.url {
                         self.event_broadcaster
-                            .send(Event::load_dont_track(url.as_str()))
-                            .unwrap();
+                            .send(Event::load_dont_track(url.as_str()))?;
                     }
                 }
                 Event::Stop => {
                     info!("processing stop event");
+
+                    self.viewport.cancel_retry();
+
+                    if let Some(save) = self.offline_save.take() {
+                        self.set_save_banner(
+                            &save,
+                            format!(
+                                "Stopped saving for offline reading, {} of {} pages saved",
+                                save.saved, save.total
+                            ),
+                        );
+                    }
+                }
+                Event::ShowUpload => {
+                    info!("processing show upload event");
+                }
+                Event::ShowPageInfo => {
+                    info!("processing show page info event");
+                }
+                Event::ShowSearch => {
+                    info!("processing show search event");
+                }
+                Event::ShowBookmarks => {
+                    info!("processing show bookmarks event");
+                }
+                Event::ToggleBookmark => {
+                    info!("processing toggle bookmark event");
+
+                    if let Some(url) = &self.url {
+                        self.bookmarks.toggle(url, self.title.as_deref());
+                    }
+
+                    self.update_bookmark_star();
+                }
+                Event::ShowHistory => {
+                    info!("processing show history event");
+                }
+                Event::ShowDownloads => {
+                    info!("processing show downloads event");
+                }
+                Event::Upload { url, upload } => {
+                    info!("processing upload event for url: {}", url);
+
+                    if let Err(e) = self.upload(&url, &upload) {
+                        warn!("upload to {} failed: {}", url, e);
+
+                        self.viewport.set_document(upload_error_document(&url, &e));
+                    }
+                }
+                Event::SetHeaderMode(header_mode) => {
+                    info!("processing set header mode event: {:?}", header_mode);
+
+                    self.clients.set_header_mode(header_mode);
+                }
+                Event::SetInlineImages(inline_images) => {
+                    info!("processing set inline images event: {}", inline_images);
+
+                    self.viewport.set_inline_images(inline_images);
+                }
+                Event::SetOffline(offline) => {
+                    info!("processing set offline event: {}", offline);
+
+                    self.offline = offline;
+
+                    // re-render so the banner and link markers match the new mode
+                    if let Some(url) = &self.url {
+                        self.event_broadcaster
+                            .send(Event::load_from_cache(url.as_str()))?;
+                    }
+                }
+                Event::SaveForOffline => {
+                    info!("processing save for offline event");
+
+                    match self.save_for_offline() {
+                        Ok(save) => {
+                            self.viewport.set_banner(Some(save.progress()));
+                            self.offline_save = Some(save);
+                        }
+                        Err(e) => {
+                            warn!("failed to save for offline: {}", e);
+
+                            self.viewport
+                                .set_banner(Some(format!("Saving for offline failed: {}", e)));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // attempt counts the automatic retries that led here, 0 for anything the user asked for
+    fn load(&mut self, url: &str, add_to_session: bool, from_cache: bool, attempt: u32) {
+        let url: Url = match url.parse() {
+            Ok(url) => url,
+            Err(e) => {
+                self.viewport.set_document(invalid_url_document(url, &e));
+                return;
+            }
+        };
+
+        let reloading = self.url.as_ref() == Some(&url);
+        self.url = Some(url.clone());
+
+        let page = match self.fetch(&url, from_cache) {
+            Ok((response, fetched)) => {
+                let page = self.page(&response);
+
+                // about pages are part of the browser, not somewhere the user went
+                if let (Inner::Success { .. }, false) = (
+                    response.header().inner(),
+                    response.url().scheme() == ABOUT_SCHEME,
+                ) {
+                    // going back, refreshing or a file changing on disk is not another visit
+                    if add_to_session && !reloading {
+                        self.history.record(response.url(), page.document.title());
+                    }
+
+                    self.search.index(response.url(), &page.document);
+                }
+
+                LoadedPage {
+                    cached: fetched,
+                    retry: self
+                        .retry_delay(&response, attempt)
+                        .map(|delay| (attempt + 1, delay, self.retry_policy.retries(attempt))),
+                    ..page
+                }
+            }
+            Err(e) => {
+                warn!("failed to fetch {}: {}", url, e);
+
+                // nothing was sent, so waiting out the backoff doesn't use up an attempt
+                LoadedPage {
+                    retry: e
+                        .downcast_ref::<BackingOff>()
+                        .map(|backing_off| (attempt, backing_off.remaining, true)),
+                    ..LoadedPage::new(&url, fetch_error_document(&url, &e))
+                }
+            }
+        };
+
+        // clients may normalize the url, e.g. adding a trailing slash to directories
+        let url = page.url;
+
+        self.viewport.set_language(page.lang);
+
+        self.page_info.set_info(page.info);
+
+        self.url = Some(url.clone());
+
+        self.title = page.document.title().map(str::to_string);
+
+        let uncached = if self.offline {
+            self.uncached_links(&page.document)
+        } else {
+            HashSet::new()
+        };
+
+        if reloading {
+            self.viewport.replace_document(page.document);
+        } else {
+            self.viewport.set_document(page.document);
+        }
+
+        self.viewport.set_uncached_links(uncached);
+        self.viewport.set_banner(page.cached.map(cached_banner));
+
+        if let Some(image) = page.image {
+            self.viewport.set_image(url.as_str(), image);
+        }
+
+        if let Some((attempt, delay, automatic)) = page.retry {
+            self.viewport
+                .set_retry(url.as_str(), attempt, delay, automatic);
+        }
+
+        self.toolbar.set_url(url.as_str());
+        self.update_bookmark_star();
+        self.watch(&url);
+
+        if add_to_session {
+            self.session_history.navigate(url.as_str());
+        }
+    }
+
+    // cached responses come back with the time they were fetched
+    fn fetch(
+        &self,
+        url: &Url,
+        from_cache: bool,
+    ) -> anyhow::Result<(Response, Option<OffsetDateTime>)> {
+        if self.offline && cache::is_remote(url) {
+            let (response, fetched) = self
+                .cache
+                .get_any(url)
+                .ok_or_else(|| NotCached(url.clone()))?;
+
+            return Ok((response, Some(fetched)));
+        }
+
+        if from_cache {
+            if let Some((response, fetched)) = self.cache.get(url) {
+                info!("rendering {} from cache", url);
+
+                return Ok((response, Some(fetched)));
+            }
+        }
+
+        let response = self.get(url)?;
+
+        if let Err(e) = self.cache.store(&response) {
+            warn!("failed to cache response for {}: {}", url, e);
+        }
+
+        Ok((response, None))
+    }
+
+    // saves the page itself, the links it has on the same capsule are queued for later frames
+    fn save_for_offline(&mut self) -> anyhow::Result<OfflineSave> {
+        let url = self
+            .url
+            .clone()
+            .ok_or_else(|| anyhow!("there is no page to save"))?;
+
+        let response = self.get(&url)?;
+
+        anyhow::ensure!(
+            self.cache.save(&response)?,
+            "{} can't be saved for offline reading",
+            url
+        );
+
+        let (document, _) = self.render(&response)?;
+        let links = cache::capsule_links(&document, response.url());
+
+        self.search.index(response.url(), &document);
+
+        Ok(OfflineSave {
+            url,
+            total: links.len() + 1,
+            pending: links.into_iter().collect(),
+            saved: 1,
+        })
+    }
+
+    // one link per frame keeps the window responsive, and stop can cancel between them
+    fn save_next_for_offline(&mut self) {
+        let mut save = match self.offline_save.take() {
+            Some(save) => save,
+            None => return,
+        };
+
+        let link = match save.pending.pop_front() {
+            Some(link) => link,
+            None => {
+                self.set_save_banner(&save, save.summary());
+
+                if self.offline && self.url.as_ref() == Some(&save.url) {
+                    if let Some(document) = self.viewport.document() {
+                        let uncached = self.uncached_links(document);
+
+                        self.viewport.set_uncached_links(uncached);
+                    }
+                }
+
+                return;
+            }
+        };
+
+        info!("saving {} for offline reading", link);
+
+        let response = self.get(&link);
+
+        // the link waits its turn until the host is ready for us again
+        if let Err(e) = &response {
+            if let Some(backing_off) = e.downcast_ref::<BackingOff>() {
+                save.pending.push_front(link);
+
+                self.set_save_banner(&save, format!("{}, {}", save.progress(), backing_off));
+                self.offline_save = Some(save);
+
+                return;
+            }
+        }
+
+        match response {
+            Ok(response) if response.header().status() == Status::SlowDown => {
+                info!("{} asked us to slow down, saving it later", link);
+
+                save.pending.push_front(link);
+            }
+            Ok(response) => match self.cache.save(&response) {
+                Ok(true) => {
+                    save.saved += 1;
+
+                    // saved pages are searchable before they are ever opened
+                    if let Ok((document, _)) = self.render(&response) {
+                        self.search.index(response.url(), &document);
+                    }
+                }
+                Ok(false) => info!("{} was not saved, only successful pages are", link),
+                Err(e) => warn!("failed to save {}: {}", link, e),
+            },
+            Err(e) => warn!("failed to save {}: {}", link, e),
+        }
+
+        self.set_save_banner(&save, save.progress());
+        self.offline_save = Some(save);
+    }
+
+    // progress is only shown while the page being saved is the one on screen
+    fn set_save_banner(&mut self, save: &OfflineSave, banner: String) {
+        if self.url.as_ref() == Some(&save.url) {
+            self.viewport.set_banner(Some(banner));
+        }
+    }
+
+    // every request goes through here, so hosts that asked us to slow down are left alone
+    fn get(&self, url: &Url) -> anyhow::Result<Response> {
+        let host = url.host_str().unwrap_or_default();
+
+        if let Some(remaining) = self.backoff.remaining(host) {
+            return Err(BackingOff {
+                host: host.to_string(),
+                remaining,
+            }
+            .into());
+        }
+
+        let response = self.clients.get(url)?;
+
+        if let Some(delay) = self.retry_policy.slow_down(response.header()) {
+            self.backoff.record(host, delay);
+        }
+
+        Ok(response)
+    }
+
+    // a host that asked us to slow down earlier keeps us waiting at least that long
+    fn retry_delay(&self, response: &Response, attempt: u32) -> Option<Duration> {
+        let host = response.url().host_str().unwrap_or_default();
+
+        let delay = self.retry_policy.delay(response.header(), attempt)?;
+
+        Some(match self.backoff.remaining(host) {
+            Some(remaining) => delay.max(remaining),
+            None => delay,
+        })
+    }
+
+    fn uncached_links(&self, document: &Document) -> HashSet<String> {
+        document
+            .lines()
+            .iter()
+            .filter_map(|line| match line {
+                Line::Link { url, .. } if cache::is_remote(url) && !self.cache.contains(url) => {
+                    Some(url.to_string())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn page(&mut self, response: &Response) -> LoadedPage {
+        let url = response.url();
+
+        if let (Some(transfer), Inner::Success { mime }) =
+            (response.transfer(), response.header().inner())
+        {
+            self.downloads.add(transfer.clone(), mime);
+        }
+
+        let (document, info) = match self.render(response) {
+            Ok(rendered) => rendered,
+            Err(e) => {
+                warn!("failed to render {}: {}", url, e);
+
+                (
+                    parse_error_document(url, &e),
+                    PageInfo::from_response(response),
+                )
+            }
+        };
+
+        let image = response
+            .mime()
+            .filter(|mime| image_types::is_supported(mime))
+            .map(|_| self.decode_image(response));
+
+        LoadedPage {
+            url: url.clone(),
+            document,
+            info,
+            lang: response.lang(),
+            image,
+            retry: None,
+            cached: None,
+        }
+    }
+
+    fn update_bookmark_star(&mut self) {
+        let bookmarked = self
+            .url
+            .as_ref()
+            .is_some_and(|url| self.bookmarks.is_bookmarked(url));
+
+        self.toolbar.set_bookmarked(bookmarked);
+    }
+
+    fn watch(&self, url: &Url) {
+        if let Some(file_watcher) = &self.file_watcher {
+            let path = match url.scheme() {
+                FILE_SCHEME => url.to_file_path().ok(),
+                _ => None,
+            };
+
+            file_watcher.watch(path);
+        }
+    }
+
+    fn render(&self, response: &Response) -> anyhow::Result<(Document, PageInfo)> {
+        let mut page_info = PageInfo::from_response(response);
+
+        let url = response.url();
+        let mime = response.mime();
+
+        if let Some(document) = status_document(response) {
+            return Ok((document, page_info));
+        }
+
+        if let Some(transfer) = response.transfer() {
+            return Ok((transfer_document(transfer), page_info));
+        }
+
+        // the image itself is shown by the viewport, the document only describes it
+        if let Some(mime) = mime.filter(|mime| image_types::is_supported(mime)) {
+            return Ok((image_document(url, mime), page_info));
+        }
+
+        if mime.is_some_and(|mime| !mime_types::is_text(mime)) {
+            return Ok((unsupported_document(url, mime), page_info));
+        }
+
+        let decoded = charset::decode(response.body().unwrap(), mime);
+        page_info.set_encoding(decoded.encoding());
+
+        let body = decoded.text().as_bytes();
+
+        // relative links in local files resolve against their directory
+        let base_url = match url.scheme() {
+            FILE_SCHEME => file::base_url(url),
+            _ => url.clone(),
+        };
+
+        let document = match (url.scheme(), mime.map(Mime::essence_str)) {
+            (FINGER_SCHEME, _) => build_preformatted_document(body, None),
+            (NEX_SCHEME, _) if nex::is_directory(url) => build_link_list_document(body, url)?,
+            (NEX_SCHEME, _) => build_preformatted_document(body, None),
+            (_, None | Some("text/gemini")) => build_document(body, &base_url)?,
+            (_, Some("text/markdown")) => build_markdown_document(body, &base_url),
+            (_, Some("text/html")) => build_html_document(body, &base_url),
+            (_, Some(_)) => build_preformatted_document(body, None),
+        };
+
+        Ok((document, page_info))
+    }
+
+    // fetches an image link expanded inline, without navigating away from the page
+    fn load_image(&mut self, url: &str) {
+        let image = match url
+            .parse()
+            .map_err(anyhow::Error::from)
+            .and_then(|url| self.fetch(&url, false))
+        {
+            Ok((response, _)) => {
+                if let (Some(transfer), Inner::Success { mime }) =
+                    (response.transfer(), response.header().inner())
+                {
+                    self.downloads.add(transfer.clone(), mime);
                 }
+
+                self.decode_image(&response)
+            }
+            Err(e) => Err(e.to_string()),
+        };
+
+        self.viewport.add_image(url, image);
+    }
+
+    fn decode_image(&self, response: &Response) -> Result<DecodedImage, String> {
+        match (response.mime(), response.body()) {
+            (Some(mime), Some(body)) if image_types::is_supported(mime) => {
+                image_types::decode(body, self.settings.max_image_dimension())
+                    .map_err(|e| format!("failed to decode image: {}", e))
+            }
+            (Some(mime), None) if image_types::is_supported(mime) => {
+                Err("image is too large to show, it was saved to downloads".to_string())
+            }
+            (Some(mime), _) => Err(format!("not a supported image: {}", mime)),
+            (None, _) => Err(format!(
+                "server responded with status: {}",
+                response.header().status()
+            )),
+        }
+    }
+
+    fn upload(&mut self, url: &str, upload: &Upload) -> anyhow::Result<()> {
+        let url: Url = url.parse()?;
+
+        let response = self.clients.upload(&url, upload)?;
+
+        match response.header().inner() {
+            Inner::Redirect { url } => {
+                self.event_broadcaster.send(Event::load(url.as_str()))?;
+            }
+            Inner::Success { mime } if response.transfer().is_some() => {
+                let transfer = response.transfer().unwrap();
+
+                self.viewport.set_document(transfer_document(transfer));
+                self.downloads.add(transfer.clone(), mime);
+            }
+            Inner::Success { mime } => {
+                info!("upload succeeded with response mime: {}", mime);
+
+                let decoded = charset::decode(response.body().unwrap(), Some(mime));
+                let document = build_document(decoded.text().as_bytes(), response.url())?;
+
+                self.viewport.set_document(document);
+            }
+            _ => {
+                self.viewport
+                    .set_document(upload_failure_document(&response));
             }
         }
 
@@ -142,14 +833,94 @@ impl DioscuriApp {
     }
 }
 
+fn cached_banner(fetched: OffsetDateTime) -> String {
+    format!(
+        "Cached copy from {} {:02}:{:02} UTC",
+        fetched.date(),
+        fetched.hour(),
+        fetched.minute()
+    )
+}
+
+fn transfer_document(transfer: &Transfer) -> Document {
+    Document::new(vec![
+        Line::heading("Downloading", 1),
+        Line::text(&format!(
+            "{} is too large or cannot be displayed, saving it to:",
+            transfer.url()
+        )),
+        Line::text(&transfer.path().display().to_string()),
+    ])
+}
+
+fn image_document(url: &Url, mime: &Mime) -> Document {
+    Document::new(vec![Line::text(&format!("{} ({})", url, mime))])
+}
+
+fn unsupported_document(url: &Url, mime: Option<&Mime>) -> Document {
+    let mime = mime.map_or("unknown".to_string(), Mime::to_string);
+
+    Document::new(vec![
+        Line::heading("Cannot display file", 1),
+        Line::text(&format!("{} has unsupported type: {}", url, mime)),
+    ])
+}
+
+fn upload_error_document(url: &str, error: &anyhow::Error) -> Document {
+    Document::new(vec![
+        Line::heading("Upload failed", 1),
+        Line::text(&format!("Could not upload to {}", url)),
+        Line::quote(&error.to_string()),
+    ])
+}
+
+fn upload_failure_document(response: &Response) -> Document {
+    let status = response.header().status();
+
+    let error = match response.header().inner() {
+        Inner::Input { prompt: error }
+        | Inner::Failure { error }
+        | Inner::ClientCertificateRequired { error } => error.clone(),
+        _ => None,
+    };
+
+    Document::new(vec![
+        Line::heading("Upload failed", 1),
+        Line::text(&format!("Server responded with status: {}", status)),
+        Line::text(&error.unwrap_or_default()),
+    ])
+}
+
 impl epi::App for DioscuriApp {
     fn name(&self) -> &str {
         "Dioscuri"
     }
 
+    fn setup(
+        &mut self,
+        _ctx: &egui::Context,
+        frame: &epi::Frame,
+        _storage: Option<&dyn epi::Storage>,
+    ) {
+        let frame = frame.clone();
+
+        self.file_watcher = Some(FileWatcher::spawn(
+            self.event_bus.broadcaster(),
+            move || frame.request_repaint(),
+        ));
+    }
+
     fn update(&mut self, ctx: &egui::Context, frame: &epi::Frame) {
-        self.process_events()
-            .expect("failed to process events from event_bus");
+        if let Err(e) = self.process_events() {
+            error!("failed to process events from event_bus: {}", e);
+        }
+
+        self.save_next_for_offline();
+
+        // events sent while processing (e.g. refresh -> load) need another frame
+        if self.event_bus.has_pending() || self.offline_save.is_some() {
+            ctx.request_repaint();
+        }
 
         egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
             self.toolbar.ui(
@@ -163,6 +934,17 @@ impl epi::App for DioscuriApp {
             self.viewport.ui(ui);
         });
 
+        self.upload_dialog.ui(ctx);
+        self.page_info.ui(ctx);
+        self.downloads.ui(ctx);
+        self.history.ui(ctx);
+        self.bookmarks.ui(ctx);
+        self.search.ui(ctx);
+
+        if self.bookmarks.take_changed() {
+            self.update_bookmark_star();
+        }
+
         frame.set_window_size(ctx.used_size());
     }
 }
@@ -0,0 +1,98 @@
+use eframe::egui;
+
+use crate::event::{Event, EventReceiver};
+use crate::response::Response;
+
+#[derive(Debug, Clone, Default)]
+pub struct PageInfo {
+    url: String,
+    status: Option<String>,
+    mime: Option<String>,
+    encoding: Option<String>,
+    lang: Option<String>,
+    deviations: Vec<String>,
+}
+
+impl PageInfo {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_response(response: &Response) -> Self {
+        let header = response.header();
+
+        Self {
+            url: response.url().to_string(),
+            status: Some(header.status().to_string()),
+            mime: response.mime().map(|mime| mime.to_string()),
+            encoding: None,
+            lang: response.lang(),
+            deviations: header.deviations().iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    pub fn set_encoding(&mut self, encoding: &str) {
+        self.encoding = Some(encoding.to_string());
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PageInfoWindow {
+    open: bool,
+    info: PageInfo,
+    event_receiver: EventReceiver,
+}
+
+impl PageInfoWindow {
+    pub fn new(event_receiver: EventReceiver) -> Self {
+        Self {
+            open: false,
+            info: Default::default(),
+            event_receiver,
+        }
+    }
+
+    pub fn set_info(&mut self, info: PageInfo) {
+        self.info = info;
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context) {
+        for event in self.event_receiver.try_iter() {
+            if let Event::ShowPageInfo = event {
+                self.open = !self.open;
+            }
+        }
+
+        let info = &self.info;
+
+        egui::Window::new("Page Info")
+            .open(&mut self.open)
+            .show(ctx, |ui| {
+                egui::Grid::new("page_info_fields").show(ui, |ui| {
+                    field(ui, "URL", Some(&info.url));
+                    field(ui, "Status", info.status.as_ref());
+                    field(ui, "MIME", info.mime.as_ref());
+                    field(ui, "Encoding", info.encoding.as_ref());
+                    field(ui, "Language", info.lang.as_ref());
+                });
+
+                if !info.deviations.is_empty() {
+                    ui.separator();
+                    ui.label("Header deviations:");
+
+                    for deviation in &info.deviations {
+                        ui.label(format!("* {}", deviation));
+                    }
+                }
+            });
+    }
+}
+
+fn field(ui: &mut egui::Ui, name: &str, value: Option<&String>) {
+    ui.label(name);
+    ui.label(value.map_or("-", String::as_str));
+    ui.end_row();
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+
+use eframe::egui;
+use log::warn;
+use url::Url;
+
+use crate::db::model::SearchResult;
+use crate::event::{Event, EventBroadcaster, EventReceiver};
+use crate::gemini::Document;
+use crate::search::SearchIndex;
+
+const RESULT_LIMIT: u32 = 50;
+
+pub struct SearchWindow {
+    open: bool,
+    index: SearchIndex,
+    query: String,
+    results: Vec<SearchResult>,
+    error: Option<String>,
+    event_broadcaster: EventBroadcaster,
+    event_receiver: EventReceiver,
+}
+
+impl fmt::Debug for SearchWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SearchWindow")
+    }
+}
+
+impl SearchWindow {
+    pub fn new(
+        index: SearchIndex,
+        event_broadcaster: EventBroadcaster,
+        event_receiver: EventReceiver,
+    ) -> Self {
+        Self {
+            open: false,
+            index,
+            query: String::new(),
+            results: vec![],
+            error: None,
+            event_broadcaster,
+            event_receiver,
+        }
+    }
+
+    pub fn index(&self, url: &Url, document: &Document) {
+        if let Err(e) = self.index.index(url, document) {
+            warn!("failed to index {}: {}", url, e);
+        }
+    }
+
+    fn search(&mut self) {
+        match self.index.search(&self.query, RESULT_LIMIT) {
+            Ok(results) => {
+                self.results = results;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context) {
+        let toggles = self
+            .event_receiver
+            .try_iter()
+            .filter(|event| matches!(event, Event::ShowSearch))
+            .count();
+
+        if toggles % 2 == 1 {
+            self.open = !self.open;
+        }
+
+        let mut searched = false;
+        let mut load = None;
+
+        egui::Window::new("Search")
+            .open(&mut self.open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Find:");
+
+                    let response = ui.text_edit_singleline(&mut self.query);
+
+                    searched = response.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+                    searched |= ui.button("Search").clicked();
+                });
+
+                ui.separator();
+
+                if self.results.is_empty() && !self.query.trim().is_empty() {
+                    ui.label("No visited pages match.");
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(360.0)
+                    .show(ui, |ui| {
+                        for result in &self.results {
+                            let link =
+                                egui::Label::new(egui::RichText::new(&result.title).underline())
+                                    .sense(egui::Sense::click());
+
+                            if ui.add(link).on_hover_text(&result.url).clicked() {
+                                load = Some(result.url.clone());
+                            }
+
+                            ui.weak(&result.url);
+                            ui.label(&result.snippet);
+                            ui.separator();
+                        }
+                    });
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+            });
+
+        if searched {
+            self.search();
+        }
+
+        if let Some(url) = load {
+            self.event_broadcaster.send(Event::load(&url)).unwrap();
+        }
+    }
+}
@@ -2,18 +2,33 @@ use eframe::egui;
 use egui::Key;
 
 use crate::event::{Event, EventBroadcaster, EventReceiver};
+use crate::header::ParseMode;
 
 #[derive(Debug, Clone)]
 pub struct Toolbar {
     url: String,
+    strict_headers: bool,
+    inline_images: bool,
+    offline: bool,
+    bookmarked: bool,
     event_broadcaster: EventBroadcaster,
     event_receiver: EventReceiver,
 }
 
 impl Toolbar {
-    pub fn new(event_broadcaster: EventBroadcaster, event_receiver: EventReceiver) -> Self {
+    pub fn new(
+        event_broadcaster: EventBroadcaster,
+        event_receiver: EventReceiver,
+        header_mode: ParseMode,
+        inline_images: bool,
+        offline: bool,
+    ) -> Self {
         Self {
             url: "".to_string(),
+            strict_headers: header_mode == ParseMode::Strict,
+            inline_images,
+            offline,
+            bookmarked: false,
             event_broadcaster,
             event_receiver,
         }
@@ -23,13 +38,13 @@ impl Toolbar {
         self.url = url.to_string();
     }
 
+    pub fn set_bookmarked(&mut self, bookmarked: bool) {
+        self.bookmarked = bookmarked;
+    }
+
     pub fn ui(&mut self, ui: &mut egui::Ui, back_enabled: bool, forward_enabled: bool) {
         for event in self.event_receiver.try_iter() {
-            if let Event::Load {
-                url,
-                add_to_session: _,
-            } = event
-            {
+            if let Event::Load { url, .. } = event {
                 self.url = url;
             }
         }
@@ -65,6 +80,83 @@ impl Toolbar {
                 self.event_broadcaster.send(Event::stop()).unwrap();
             }
 
+            if ui.button("U").clicked() {
+                self.event_broadcaster.send(Event::show_upload()).unwrap();
+            }
+
+            if ui.button("I").clicked() {
+                self.event_broadcaster
+                    .send(Event::show_page_info())
+                    .unwrap();
+            }
+
+            if ui.button("D").clicked() {
+                self.event_broadcaster
+                    .send(Event::show_downloads())
+                    .unwrap();
+            }
+
+            if ui.button("S").on_hover_text("Save for offline").clicked() {
+                self.event_broadcaster
+                    .send(Event::save_for_offline())
+                    .unwrap();
+            }
+
+            if ui.button("B").on_hover_text("Bookmarks").clicked() {
+                self.event_broadcaster
+                    .send(Event::show_bookmarks())
+                    .unwrap();
+            }
+
+            if ui
+                .button("F")
+                .on_hover_text("Search visited pages")
+                .clicked()
+            {
+                self.event_broadcaster.send(Event::show_search()).unwrap();
+            }
+
+            if ui.button("Y").on_hover_text("History").clicked() {
+                self.event_broadcaster.send(Event::show_history()).unwrap();
+            }
+
+            // lets capsule authors validate their server's headers
+            if ui.checkbox(&mut self.strict_headers, "Strict").changed() {
+                let header_mode = if self.strict_headers {
+                    ParseMode::Strict
+                } else {
+                    ParseMode::Lenient
+                };
+
+                self.event_broadcaster
+                    .send(Event::set_header_mode(header_mode))
+                    .unwrap();
+            }
+
+            if ui.checkbox(&mut self.inline_images, "Images").changed() {
+                self.event_broadcaster
+                    .send(Event::set_inline_images(self.inline_images))
+                    .unwrap();
+            }
+
+            if ui.checkbox(&mut self.offline, "Offline").changed() {
+                self.event_broadcaster
+                    .send(Event::set_offline(self.offline))
+                    .unwrap();
+            }
+
+            let star = if self.bookmarked { "★" } else { "☆" };
+
+            if ui
+                .button(star)
+                .on_hover_text("Bookmark this page")
+                .clicked()
+            {
+                self.event_broadcaster
+                    .send(Event::toggle_bookmark())
+                    .unwrap();
+            }
+
             let response = ui.text_edit_singleline(&mut self.url);
 
             if response.lost_focus() && ui.input().key_pressed(Key::Enter) {
@@ -0,0 +1,135 @@
+use eframe::egui;
+use url::Url;
+
+use crate::event::{Event, EventBroadcaster, EventReceiver};
+use crate::titan::Upload;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Source {
+    File,
+    Text,
+}
+
+#[derive(Debug, Clone)]
+pub struct UploadDialog {
+    open: bool,
+    url: String,
+    mime: String,
+    token: String,
+    source: Source,
+    path: String,
+    text: String,
+    error: Option<String>,
+    event_broadcaster: EventBroadcaster,
+    event_receiver: EventReceiver,
+}
+
+impl UploadDialog {
+    pub fn new(event_broadcaster: EventBroadcaster, event_receiver: EventReceiver) -> Self {
+        Self {
+            open: false,
+            url: "".to_string(),
+            mime: mime::TEXT_PLAIN.to_string(),
+            token: "".to_string(),
+            source: Source::Text,
+            path: "".to_string(),
+            text: "".to_string(),
+            error: None,
+            event_broadcaster,
+            event_receiver,
+        }
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context) {
+        for event in self.event_receiver.try_iter() {
+            match event {
+                Event::Load { url, .. } if !self.open => {
+                    self.url = titan_url_for(&url).unwrap_or(url);
+                }
+                Event::ShowUpload => {
+                    self.open = true;
+                    self.error = None;
+                }
+                _ => {}
+            }
+        }
+
+        let mut open = self.open;
+
+        egui::Window::new("Upload")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("upload_fields").show(ui, |ui| {
+                    ui.label("URL");
+                    ui.text_edit_singleline(&mut self.url);
+                    ui.end_row();
+
+                    ui.label("MIME");
+                    ui.text_edit_singleline(&mut self.mime);
+                    ui.end_row();
+
+                    ui.label("Token");
+                    ui.text_edit_singleline(&mut self.token);
+                    ui.end_row();
+                });
+
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.source, Source::Text, "Text");
+                    ui.radio_value(&mut self.source, Source::File, "File");
+                });
+
+                match self.source {
+                    Source::Text => {
+                        ui.code_editor(&mut self.text);
+                    }
+                    Source::File => {
+                        ui.horizontal(|ui| {
+                            ui.label("Path");
+                            ui.text_edit_singleline(&mut self.path);
+                        });
+                    }
+                }
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                if ui.button("Upload").clicked() {
+                    match self.build_upload() {
+                        Ok(upload) => {
+                            self.event_broadcaster
+                                .send(Event::upload(&self.url, upload))
+                                .unwrap();
+
+                            self.error = None;
+                            self.open = false;
+                        }
+                        Err(e) => self.error = Some(e.to_string()),
+                    }
+                }
+            });
+
+        // the upload button may have closed the window from inside the closure
+        self.open = open && self.open;
+    }
+
+    fn build_upload(&self) -> anyhow::Result<Upload> {
+        let mime = self.mime.parse()?;
+
+        let body = match self.source {
+            Source::Text => self.text.as_bytes().to_vec(),
+            Source::File => std::fs::read(&self.path)?,
+        };
+
+        Ok(Upload::new(mime, Some(&self.token), body))
+    }
+}
+
+fn titan_url_for(url: &str) -> Option<String> {
+    let mut url: Url = url.parse().ok()?;
+
+    url.set_scheme("titan").ok()?;
+
+    Some(url.to_string())
+}
@@ -1,13 +1,39 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
 use eframe::egui;
 use egui::RichText;
 
 use crate::event::{Event, EventBroadcaster};
 use crate::gemini::{Document, Line};
+use crate::images::{is_image_url, DecodedImage};
+use crate::language;
+use crate::ui::fonts::FontFallbacks;
 use crate::ui::highlighter::SyntaxHighlighter;
+use crate::ui::images::{CachedImage, ImageCache};
+
+// a countdown to reloading the page, manual ones only say when trying again makes sense
+#[derive(Debug)]
+struct Retry {
+    url: String,
+    attempt: u32,
+    deadline: Instant,
+    automatic: bool,
+}
 
 #[derive(Debug)]
 pub struct Viewport {
     document: Option<Document>,
+    scroll_to_top: bool,
+    language: Option<String>,
+    fonts: FontFallbacks,
+    image: Option<String>,
+    images: ImageCache,
+    inline_images: bool,
+    expanded: HashSet<String>,
+    retry: Option<Retry>,
+    banner: Option<String>,
+    uncached: HashSet<String>,
     highlighter: SyntaxHighlighter,
     event_broadcaster: EventBroadcaster,
 }
@@ -16,6 +42,16 @@ impl Viewport {
     pub fn new(highlighter: SyntaxHighlighter, event_broadcaster: EventBroadcaster) -> Self {
         Self {
             document: None,
+            scroll_to_top: false,
+            language: None,
+            fonts: FontFallbacks::new(),
+            image: None,
+            images: ImageCache::new(),
+            inline_images: false,
+            expanded: HashSet::new(),
+            retry: None,
+            banner: None,
+            uncached: HashSet::new(),
             highlighter,
             event_broadcaster,
         }
@@ -23,56 +59,246 @@ impl Viewport {
 
     pub fn set_document(&mut self, document: Document) {
         self.document = Some(document);
+        self.image = None;
+        self.expanded.clear();
+        self.retry = None;
+        self.banner = None;
+        self.uncached.clear();
+        self.scroll_to_top = true;
+    }
+
+    // swaps the document without moving the scroll position, used when reloading a page
+    pub fn replace_document(&mut self, document: Document) {
+        self.document = Some(document);
+        self.image = None;
+        self.retry = None;
+        self.banner = None;
+        self.uncached.clear();
+    }
+
+    pub fn document(&self) -> Option<&Document> {
+        self.document.as_ref()
+    }
+
+    pub fn set_banner(&mut self, banner: Option<String>) {
+        self.banner = banner;
+    }
+
+    // links that can't be followed while offline
+    pub fn set_uncached_links(&mut self, uncached: HashSet<String>) {
+        self.uncached = uncached;
+    }
+
+    // counts down and reloads the page, for servers asking us to slow down or failing for now
+    pub fn set_retry(&mut self, url: &str, attempt: u32, delay: Duration, automatic: bool) {
+        self.retry = Instant::now().checked_add(delay).map(|deadline| Retry {
+            url: url.to_string(),
+            attempt,
+            deadline,
+            automatic,
+        });
+    }
+
+    pub fn cancel_retry(&mut self) {
+        self.retry = None;
     }
 
-    pub fn ui(&self, ui: &mut egui::Ui) {
+    // shows an image above the document, for responses that are images themselves
+    pub fn set_image(&mut self, url: &str, image: Result<DecodedImage, String>) {
+        self.add_image(url, image);
+        self.image = Some(url.to_string());
+    }
+
+    pub fn add_image(&mut self, url: &str, image: Result<DecodedImage, String>) {
+        match image {
+            Ok(image) => self.images.set_image(url, image),
+            Err(error) => self.images.set_failed(url, &error),
+        }
+    }
+
+    pub fn set_inline_images(&mut self, inline_images: bool) {
+        self.inline_images = inline_images;
+    }
+
+    pub fn set_language(&mut self, language: Option<String>) {
+        self.language = language;
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
         if self.document.is_none() {
             return;
         }
 
+        let right_to_left = self
+            .language
+            .as_deref()
+            .is_some_and(language::is_right_to_left);
+
+        if let Some(lang) = &self.language {
+            self.fonts.ensure(ui.ctx(), language::script(lang));
+        }
+
+        self.images.upload(ui.ctx());
+
+        self.retry_ui(ui);
+
+        if let Some(banner) = &self.banner {
+            ui.label(RichText::new(banner).italics());
+            ui.separator();
+        }
+
+        // egui has no bidi support, so right-to-left pages are at least aligned to the right
+        let layout = if right_to_left {
+            egui::Layout::top_down(egui::Align::Max)
+        } else {
+            egui::Layout::top_down(egui::Align::Min)
+        };
+
         let lines = self.document.as_ref().unwrap().lines();
 
-        egui::ScrollArea::vertical().show(ui, |ui| {
-            for line in lines {
-                match line {
-                    Line::Text { content } => {
-                        ui.label(content);
-                    }
-                    Line::Link { url, link_name } => {
-                        let response = if let Some(link_name) = link_name {
-                            ui.hyperlink_to(link_name, url)
-                        } else {
-                            ui.hyperlink(url)
-                        };
-
-                        if response.clicked() {
-                            self.event_broadcaster
-                                .send(Event::load(url.as_str()))
-                                .unwrap();
+        let mut scroll_area = egui::ScrollArea::vertical();
+
+        if self.scroll_to_top {
+            scroll_area = scroll_area.vertical_scroll_offset(0.0);
+            self.scroll_to_top = false;
+        }
+
+        scroll_area.show(ui, |ui| {
+            ui.with_layout(layout, |ui| {
+                if let Some(url) = &self.image {
+                    show_image(ui, self.images.get(url));
+                }
+
+                for line in lines {
+                    match line {
+                        Line::Text { content } => {
+                            ui.label(content);
                         }
-                    }
-                    Line::Heading { content, level: _ } => {
-                        ui.label(egui::RichText::new(content).heading());
-                    }
-                    Line::Quote { content } => {
-                        ui.label(format!("| {}", content));
-                    }
-                    Line::UnorderedListItem { content } => {
-                        ui.label(format!("* {}", content));
-                    }
-                    Line::Preformatted { alt_text, lines } => {
-                        let content = extract_content_from_preformatted_line(lines);
+                        Line::Link { url, link_name } => {
+                            let key = url.to_string();
+
+                            let response = if self.uncached.contains(&key) {
+                                let name = link_name.as_deref().unwrap_or(&key);
+
+                                ui.hyperlink_to(
+                                    RichText::new(format!("{} (not saved)", name)).strikethrough(),
+                                    url,
+                                )
+                            } else if let Some(link_name) = link_name {
+                                ui.hyperlink_to(link_name, url)
+                            } else {
+                                ui.hyperlink(url)
+                            };
+
+                            if response.clicked() && self.inline_images && is_image_url(url) {
+                                // clicking an expanded image link collapses it again
+                                if !self.expanded.remove(&key) {
+                                    // failures are fetched again, they may have been passing ones
+                                    if !self.images.contains(&key) || self.images.is_failed(&key) {
+                                        self.images.set_loading(&key);
+                                        self.event_broadcaster
+                                            .send(Event::load_image(&key))
+                                            .unwrap();
+                                    }
+
+                                    self.expanded.insert(key.clone());
+                                }
+                            } else if response.clicked() {
+                                self.event_broadcaster
+                                    .send(Event::load(url.as_str()))
+                                    .unwrap();
+                            }
+
+                            if self.expanded.contains(&key) {
+                                show_image(ui, self.images.get(&key));
+                            }
+                        }
+                        Line::Heading { content, level: _ } => {
+                            ui.label(egui::RichText::new(content).heading());
+                        }
+                        Line::Quote { content } => {
+                            ui.label(format!("| {}", content));
+                        }
+                        Line::UnorderedListItem { content } => {
+                            ui.label(format!("* {}", content));
+                        }
+                        Line::Preformatted { alt_text, lines } => {
+                            let content = extract_content_from_preformatted_line(lines);
 
-                        if let Some(alt_text) = alt_text {
-                            ui.label(self.highlighter.highlight(alt_text, &content));
-                        } else {
-                            ui.label(RichText::new(content).monospace());
+                            if let Some(alt_text) = alt_text {
+                                ui.label(self.highlighter.highlight(alt_text, &content));
+                            } else {
+                                ui.label(RichText::new(content).monospace());
+                            }
                         }
                     }
                 }
-            }
+            });
         });
     }
+
+    fn retry_ui(&mut self, ui: &mut egui::Ui) {
+        let (url, attempt, automatic, remaining) = match &self.retry {
+            Some(retry) => (
+                retry.url.clone(),
+                retry.attempt,
+                retry.automatic,
+                retry.deadline.saturating_duration_since(Instant::now()),
+            ),
+            None => return,
+        };
+
+        let mut retry = automatic && remaining.is_zero();
+        let mut cancel = false;
+
+        ui.horizontal(|ui| {
+            let label = match (automatic, remaining.is_zero()) {
+                (true, _) => format!("Retrying in {}s", remaining.as_secs() + 1),
+                (false, false) => format!(
+                    "The server asked us to wait {}s before trying again",
+                    remaining.as_secs() + 1
+                ),
+                (false, true) => "The server is ready to be tried again".to_string(),
+            };
+
+            ui.label(label);
+            retry |= ui.button("Retry now").clicked();
+            cancel = ui.button("Cancel").clicked();
+        });
+
+        if retry {
+            self.retry = None;
+            self.event_broadcaster
+                .send(Event::retry(&url, attempt))
+                .unwrap();
+        } else if cancel {
+            self.retry = None;
+        } else if !remaining.is_zero() {
+            ui.ctx().request_repaint();
+        }
+    }
+}
+
+// images wider than the viewport are scaled down to fit
+fn show_image(ui: &mut egui::Ui, image: Option<&CachedImage>) {
+    match image {
+        Some(CachedImage::Ready(texture)) => {
+            let mut size = texture.size_vec2();
+            let available = ui.available_width();
+
+            if size.x > available {
+                size *= available / size.x;
+            }
+
+            ui.image(texture, size);
+        }
+        Some(CachedImage::Failed(error)) => {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        Some(CachedImage::Loading) | None => {
+            ui.label("Loading image...");
+        }
+    }
 }
 
 fn extract_content_from_preformatted_line(lines: &[Line]) -> String {
@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crossbeam::channel::{unbounded, RecvTimeoutError, Sender};
+use log::{info, warn};
+
+use crate::event::{Event, EventBroadcaster};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+type Watched = Option<(PathBuf, Option<Stamp>)>;
+
+// the length catches writes landing within the same tick of a coarse mtime
+type Stamp = (SystemTime, u64);
+
+// polls the modification time and length of a single path and asks for a refresh when it changes
+#[derive(Debug)]
+pub struct FileWatcher {
+    sender: Sender<Watched>,
+}
+
+impl FileWatcher {
+    pub fn spawn(
+        event_broadcaster: EventBroadcaster,
+        request_repaint: impl Fn() + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = unbounded::<Watched>();
+
+        thread::spawn(move || {
+            let mut watched: Watched = None;
+
+            loop {
+                match receiver.recv_timeout(POLL_INTERVAL) {
+                    Ok(next) => watched = next,
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+
+                if let Some((path, last_stamp)) = &mut watched {
+                    let stamp = stamp(path);
+
+                    if stamp != *last_stamp {
+                        info!("watched file changed: {}", path.display());
+
+                        *last_stamp = stamp;
+
+                        if event_broadcaster.send(Event::refresh()).is_err() {
+                            break;
+                        }
+
+                        request_repaint();
+                    }
+                }
+            }
+
+            info!("file watcher stopped");
+        });
+
+        Self { sender }
+    }
+
+    pub fn watch(&self, path: Option<PathBuf>) {
+        let watched = path.map(|path| {
+            let stamp = stamp(&path);
+
+            (path, stamp)
+        });
+
+        if self.sender.send(watched).is_err() {
+            warn!("file watcher is no longer running");
+        }
+    }
+}
+
+fn stamp(path: &Path) -> Option<Stamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs;
+
+    fn set_modified(path: &Path, modified: SystemTime) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_watch_sends_refresh_on_change() {
+        let path = std::env::temp_dir().join("dioscuri_test_watch_sends_refresh_on_change.gmi");
+        fs::write(&path, "# Before").unwrap();
+
+        let (tx, rx) = unbounded();
+        let watcher = FileWatcher::spawn(tx, || {});
+
+        watcher.watch(Some(path.clone()));
+
+        // same length, so only the mtime tells the versions apart
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        fs::write(&path, "# After!").unwrap();
+        set_modified(&path, modified + Duration::from_secs(10));
+
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(event, Event::Refresh));
+
+        watcher.watch(None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_watch_sends_refresh_on_change_within_mtime_tick() {
+        let path = std::env::temp_dir().join("dioscuri_test_watch_within_mtime_tick.gmi");
+        fs::write(&path, "# Before").unwrap();
+
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+
+        let (tx, rx) = unbounded();
+        let watcher = FileWatcher::spawn(tx, || {});
+
+        watcher.watch(Some(path.clone()));
+
+        // a coarse filesystem would report the same mtime for a write this quick
+        fs::write(&path, "# After, with a different length").unwrap();
+        set_modified(&path, modified);
+
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(event, Event::Refresh));
+
+        watcher.watch(None);
+        fs::remove_file(&path).unwrap();
+    }
+}